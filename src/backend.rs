@@ -0,0 +1,76 @@
+use chrono::NaiveDate;
+use rusty_money::iso::Currency;
+
+use crate::{BiddingZone, Diagnostic, Error, SpotPrice, ZonePrices};
+
+mod client;
+mod dataportal;
+mod legacy;
+
+#[cfg(feature = "entsoe")]
+pub(crate) use client::USER_AGENT;
+pub use client::{NordPoolClient, NordPoolClientBuilder};
+pub use dataportal::DataPortal;
+pub use legacy::Legacy;
+#[cfg(feature = "import")]
+pub(crate) use legacy::{is_placeholder, parse_price, resolve_local_time};
+
+/// A Nord Pool API schema: where the prices of a delivery day are published
+/// and how to parse them.
+pub trait Backend: Send + Sync {
+    /// Scheme and host of the public API, e.g. `https://www.nordpoolgroup.com`.
+    fn base_url(&self) -> &'static str;
+
+    /// URL of the prices of `zones` in `currency` for the delivery day `date`
+    /// on the API at `base_url`.
+    fn url(
+        &self,
+        base_url: &str,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> String;
+
+    /// Parses a response body into the spot prices per kWh of `zone` and the
+    /// entries that could not be parsed.
+    fn parse(
+        &self,
+        body: &[u8],
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error>;
+
+    /// Parses a response body into the spot prices of each of `zones`. The
+    /// default parses the body once per zone.
+    fn parse_many(
+        &self,
+        body: &[u8],
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+    ) -> Result<ZonePrices, Error> {
+        zones
+            .iter()
+            .map(|&zone| Ok((zone, self.parse(body, zone, currency)?)))
+            .collect()
+    }
+
+    /// Parses a response body into the spot prices of every zone it
+    /// publishes. The default parses the body once per zone.
+    fn parse_all(&self, body: &[u8], currency: &'static Currency) -> Result<ZonePrices, Error> {
+        published(|zone| self.parse(body, zone, currency))
+    }
+}
+
+/// The prices of each of [`BiddingZone::ALL`] parsed with `parse`, leaving
+/// out the zones it fails with [`Error::UnknownArea`] for.
+pub(crate) fn published(
+    parse: impl Fn(BiddingZone) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error>,
+) -> Result<ZonePrices, Error> {
+    BiddingZone::ALL
+        .iter()
+        .filter_map(|&zone| match parse(zone) {
+            Err(Error::UnknownArea(_)) => None,
+            result => Some(result.map(|prices| (zone, prices))),
+        })
+        .collect()
+}
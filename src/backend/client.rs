@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{Date, NaiveDate};
+use chrono_tz::Tz;
+use reqwest::{Certificate, Proxy, StatusCode};
+use rusty_money::iso::Currency;
+
+use super::{Backend, DataPortal};
+use crate::{
+    BiddingZone, Diagnostic, Error, LenientPrices, PriceSource, Resolution, RetryPolicy, SpotPrice,
+    Tariff, TotalPrice, ZonePrices, SUPPORTED_CURRENCIES,
+};
+
+pub(crate) const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Fetches spot prices from Nord Pool, by default from the Data Portal API.
+///
+/// The client keeps one connection pool for all requests, so create it once
+/// and reuse it.
+pub struct NordPoolClient {
+    backend: Box<dyn Backend>,
+    base_url: String,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl NordPoolClient {
+    pub fn new() -> Self {
+        Self::with_backend(DataPortal)
+    }
+
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        Self::builder()
+            .backend(backend)
+            .build()
+            .expect("default HTTP client")
+    }
+
+    pub fn builder() -> NordPoolClientBuilder {
+        NordPoolClientBuilder::default()
+    }
+
+    /// Like [`get_prices`](crate::get_prices), using this client.
+    pub async fn get_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        end_date: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<Vec<TotalPrice>, Error> {
+        crate::get_prices_from(self, zone, currency, end_date, resolution, tariff).await
+    }
+
+    /// Like [`get_prices_lenient`](crate::get_prices_lenient), using this
+    /// client.
+    pub async fn get_prices_lenient(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        end_date: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<LenientPrices, Error> {
+        crate::get_prices_lenient_from(self, zone, currency, end_date, resolution, tariff).await
+    }
+
+    /// Like [`get_prices_range`](crate::get_prices_range), using this
+    /// client.
+    pub async fn get_prices_range(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        from: Date<Tz>,
+        to: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<Vec<TotalPrice>, Error> {
+        crate::get_prices_range_from(self, zone, currency, from, to, resolution, tariff).await
+    }
+
+    /// Like [`get_prices_many`](crate::get_prices_many), using this client.
+    pub async fn get_prices_many(
+        &self,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        end_date: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+        crate::get_prices_many_from(self, zones, currency, end_date, resolution, tariff).await
+    }
+
+    /// Like [`get_prices_all`](crate::get_prices_all), using this client.
+    pub async fn get_prices_all(
+        &self,
+        currency: &'static Currency,
+        end_date: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+        crate::get_prices_all_from(self, currency, end_date, resolution, tariff).await
+    }
+}
+
+impl Default for NordPoolClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NordPoolClient {
+    /// Requests the prices of `zones` and reads the response body, retrying
+    /// as configured.
+    async fn fetch(
+        &self,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<Vec<u8>, Error> {
+        if !SUPPORTED_CURRENCIES.contains(&currency) {
+            return Err(Error::UnsupportedCurrency(currency.iso_alpha_code));
+        }
+        let url = self.backend.url(&self.base_url, zones, currency, date);
+        let (status, body) = self.retry.get(&self.http, &url).await?;
+        // The Data Portal answers 204 until the auction results are published.
+        if status == StatusCode::NO_CONTENT {
+            return Err(Error::NotPublished(date));
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl PriceSource for NordPoolClient {
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        let body = self.fetch(&[zone], currency, date).await?;
+        self.backend.parse(&body, zone, currency)
+    }
+
+    /// Fetches all `zones` with a single request.
+    async fn spot_prices_many(
+        &self,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let body = self.fetch(zones, currency, date).await?;
+        self.backend.parse_many(&body, zones, currency)
+    }
+
+    /// Requests all zones and keeps those in the response.
+    async fn spot_prices_all(
+        &self,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let body = self.fetch(&BiddingZone::ALL, currency, date).await?;
+        self.backend.parse_all(&body, currency)
+    }
+}
+
+/// Configures a [`NordPoolClient`].
+///
+/// The HTTP options are ignored when an existing [`reqwest::Client`] is
+/// passed with [`NordPoolClientBuilder::client`].
+pub struct NordPoolClientBuilder {
+    backend: Box<dyn Backend>,
+    base_url: Option<String>,
+    client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: String,
+    proxies: Vec<Proxy>,
+    root_certificates: Vec<Certificate>,
+    accept_invalid_certs: bool,
+    retry: RetryPolicy,
+}
+
+impl Default for NordPoolClientBuilder {
+    fn default() -> Self {
+        Self {
+            backend: Box::new(DataPortal),
+            base_url: None,
+            client: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: USER_AGENT.to_string(),
+            proxies: vec![],
+            root_certificates: vec![],
+            accept_invalid_certs: false,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl NordPoolClientBuilder {
+    /// The API schema to use, [`DataPortal`] by default.
+    pub fn backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Box::new(backend);
+        self
+    }
+
+    /// Sends requests to `base_url` instead of the public API of the
+    /// backend, e.g. a mirror or a local mock server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sends requests with `client`, e.g. to share a connection pool.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Timeout of each request, from connecting until the body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` header, `nordpool/<version>` by default.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system roots, e.g. for a
+    /// TLS-intercepting proxy.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Accepts invalid TLS certificates. Only use this for testing.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// How failed requests are retried, [`RetryPolicy::default`] unless set.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fails with [`Error::Transport`] if the HTTP client cannot be built,
+    /// e.g. when the TLS backend fails to initialize.
+    pub fn build(self) -> Result<NordPoolClient, Error> {
+        let http = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = reqwest::Client::builder()
+                    .user_agent(self.user_agent)
+                    .danger_accept_invalid_certs(self.accept_invalid_certs);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                builder.build()?
+            }
+        };
+        let base_url = self
+            .base_url
+            .as_deref()
+            .unwrap_or(self.backend.base_url())
+            .trim_end_matches('/')
+            .to_string();
+        Ok(NordPoolClient {
+            backend: self.backend,
+            base_url,
+            http,
+            retry: self.retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Stockholm;
+
+    use super::NordPoolClient;
+    use crate::mock::{MockServer, PRICES};
+    use crate::{BiddingZone, Error, PriceSource, Resolution, Tariff};
+
+    #[tokio::test]
+    async fn base_url_and_user_agent() {
+        let server = MockServer::start(vec![("200 OK", PRICES), ("204 No Content", "")]).await;
+        let client = NordPoolClient::builder()
+            .base_url(format!("{}/", server.url))
+            .user_agent("test-agent")
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let tariff = Tariff::default();
+        let date = Stockholm.ymd(2024, 10, 27);
+        let prices = client
+            .get_prices(
+                BiddingZone::SE3,
+                tariff.currency,
+                date,
+                Resolution::Hour,
+                &tariff,
+            )
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 25);
+        let result = client
+            .get_prices(
+                BiddingZone::SE3,
+                tariff.currency,
+                date,
+                Resolution::Hour,
+                &tariff,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::NotPublished(_))));
+
+        let requests = server.requests.lock().unwrap();
+        assert!(requests[0].starts_with(
+            "GET /api/DayAheadPrices?date=2024-10-27&market=DayAhead&deliveryArea=SE3&currency=SEK "
+        ));
+        assert!(requests[0]
+            .lines()
+            .any(|line| line.eq_ignore_ascii_case("user-agent: test-agent")));
+    }
+
+    #[tokio::test]
+    async fn many_zones_in_one_request() {
+        let server = MockServer::start(vec![("200 OK", PRICES), ("200 OK", PRICES)]).await;
+        let client = NordPoolClient::builder()
+            .base_url(&server.url)
+            .build()
+            .unwrap();
+        let tariff = Tariff::default();
+        let date = Stockholm.ymd(2024, 10, 27);
+        let zones = [BiddingZone::SE3, BiddingZone::NO1];
+        let prices = client
+            .spot_prices_many(&zones, tariff.currency, date.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[&BiddingZone::SE3].0.len(), 25);
+        let (no1, diagnostics) = &prices[&BiddingZone::NO1];
+        assert_eq!((no1.len(), diagnostics.len()), (24, 1));
+        {
+            let requests = server.requests.lock().unwrap();
+            assert_eq!(requests.len(), 1);
+            assert!(requests[0].contains("&deliveryArea=SE3,NO1&"));
+        }
+
+        // NO1 is missing a price.
+        let result = client
+            .get_prices_many(&zones, tariff.currency, date, Resolution::Hour, &tariff)
+            .await;
+        assert!(matches!(result, Err(Error::MissingPrice(_))));
+    }
+}
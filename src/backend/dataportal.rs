@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use rusty_money::iso::Currency;
+use serde::Deserialize;
+
+use super::{published, Backend};
+use crate::{BiddingZone, Diagnostic, Error, Money, SpotPrice, ZonePrices};
+
+const DATAPORTAL_URL: &str = "https://dataportal-api.nordpoolgroup.com";
+
+/// The Nord Pool Data Portal API, which publishes day-ahead prices in the
+/// market time unit of the auction, 15 minutes since October 2025. Several
+/// delivery areas can be requested at once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataPortal;
+
+impl Backend for DataPortal {
+    fn base_url(&self) -> &'static str {
+        DATAPORTAL_URL
+    }
+
+    fn url(
+        &self,
+        base_url: &str,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> String {
+        let areas: Vec<_> = zones.iter().map(|z| z.delivery_area()).collect();
+        format!(
+            "{base_url}/api/DayAheadPrices?date={}&market=DayAhead&deliveryArea={}&currency={}",
+            date.format("%Y-%m-%d"),
+            areas.join(","),
+            currency.iso_alpha_code
+        )
+    }
+
+    fn parse(
+        &self,
+        body: &[u8],
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        parse_response(&serde_json::from_slice(body)?, zone, currency)
+    }
+
+    fn parse_many(
+        &self,
+        body: &[u8],
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+    ) -> Result<ZonePrices, Error> {
+        let response = serde_json::from_slice(body)?;
+        zones
+            .iter()
+            .map(|&zone| Ok((zone, parse_response(&response, zone, currency)?)))
+            .collect()
+    }
+
+    fn parse_all(&self, body: &[u8], currency: &'static Currency) -> Result<ZonePrices, Error> {
+        let response = serde_json::from_slice(body)?;
+        published(|zone| parse_response(&response, zone, currency))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    currency: String,
+    multi_area_entries: Vec<Entry>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    delivery_start: DateTime<Utc>,
+    delivery_end: DateTime<Utc>,
+    /// Price per MWh by delivery area, `null` if not published.
+    entry_per_area: HashMap<String, Option<Decimal>>,
+}
+
+/// Parses the spot prices per kWh of `zone` and the entries that could not be
+/// parsed.
+fn parse_response(
+    response: &Response,
+    zone: BiddingZone,
+    currency: &'static Currency,
+) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+    let area = zone.delivery_area();
+    if !response
+        .multi_area_entries
+        .iter()
+        .any(|e| e.entry_per_area.contains_key(area))
+    {
+        return Err(Error::UnknownArea(zone.name().to_string()));
+    }
+    // Prices are converted by Nord Pool, so any other currency means the
+    // request was not honoured.
+    if response.currency != currency.iso_alpha_code {
+        return Err(Error::Document(format!(
+            "prices in {} instead of the requested {}",
+            response.currency, currency.iso_alpha_code
+        )));
+    }
+    let tz = zone.timezone();
+    let mut prices = Vec::with_capacity(response.multi_area_entries.len());
+    let mut diagnostics = vec![];
+    for entry in &response.multi_area_entries {
+        let start_time = entry.delivery_start.with_timezone(&tz);
+        match entry.entry_per_area.get(area) {
+            Some(Some(price)) => prices.push(SpotPrice {
+                start_time,
+                duration: entry.delivery_end - entry.delivery_start,
+                price: Money::from_decimal(*price, currency) / 1000,
+            }),
+            _ => diagnostics.push(Diagnostic {
+                start_time: start_time.naive_local(),
+                error: Error::MissingPrice(start_time.naive_local()),
+            }),
+        }
+    }
+    Ok((prices, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use chrono_tz::Europe::{Berlin, Stockholm};
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::{Currency, EUR, SEK};
+
+    use crate::{BiddingZone, Diagnostic, Error, SpotPrice};
+
+    fn parse(
+        fixture: &str,
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        super::parse_response(&serde_json::from_str(fixture).unwrap(), zone, currency)
+    }
+
+    const DAYLIGHT_SAVING_ENDS: &str =
+        include_str!("../../fixtures/dataportal-2024-10-27-SE3-NO1-SEK.json");
+    const QUARTER_HOURS: &str = include_str!("../../fixtures/dataportal-2025-10-02-GER-EUR.json");
+
+    #[test]
+    fn daylight_saving_ends() {
+        let (prices, diagnostics) = parse(DAYLIGHT_SAVING_ENDS, BiddingZone::SE3, SEK).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(prices.len(), 25);
+        assert_eq!(
+            prices[0].start_time,
+            Stockholm.ymd(2024, 10, 27).and_hms(0, 0, 0)
+        );
+        assert_eq!(prices[0].price.amount(), &dec!(0.32145));
+        assert_eq!(
+            prices[2].start_time.to_rfc3339(),
+            "2024-10-27T02:00:00+02:00"
+        );
+        assert_eq!(
+            prices[3].start_time.to_rfc3339(),
+            "2024-10-27T02:00:00+01:00"
+        );
+        for pair in prices.windows(2) {
+            assert_eq!(pair[0].end_time(), pair[1].start_time);
+        }
+
+        let (prices, diagnostics) = parse(DAYLIGHT_SAVING_ENDS, BiddingZone::NO1, SEK).unwrap();
+        assert_eq!(prices.len(), 24);
+        assert!(matches!(diagnostics[0].error, Error::MissingPrice(_)));
+
+        let result = parse(DAYLIGHT_SAVING_ENDS, BiddingZone::FI, SEK);
+        assert!(matches!(result, Err(Error::UnknownArea(area)) if area == "FI"));
+        let result = parse(DAYLIGHT_SAVING_ENDS, BiddingZone::SE3, EUR);
+        assert!(matches!(result, Err(Error::Document(message))
+            if message == "prices in SEK instead of the requested EUR"));
+    }
+
+    #[test]
+    fn quarter_hours() {
+        let (prices, diagnostics) = parse(QUARTER_HOURS, BiddingZone::DE, EUR).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(prices.len(), 96);
+        assert!(prices.iter().all(|p| p.duration == Duration::minutes(15)));
+        assert_eq!(
+            prices[1].start_time,
+            Berlin.ymd(2025, 10, 2).and_hms(0, 15, 0)
+        );
+        assert_eq!(prices[95].price.amount(), &dec!(-0.00512));
+    }
+}
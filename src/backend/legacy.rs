@@ -0,0 +1,352 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use rusty_money::iso::Currency;
+use serde::{Deserialize, Deserializer};
+
+use super::{published, Backend};
+use crate::{BiddingZone, Diagnostic, Error, Money, Resolution, SpotPrice, ZonePrices};
+
+const NORDPOOL_URL: &str = "https://www.nordpoolgroup.com";
+
+/// The marketdata page endpoint behind the old Nord Pool website, which
+/// publishes hourly prices of all zones in one table, whichever zones are
+/// requested.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Legacy;
+
+impl Backend for Legacy {
+    fn base_url(&self) -> &'static str {
+        NORDPOOL_URL
+    }
+
+    fn url(
+        &self,
+        base_url: &str,
+        _zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> String {
+        format!(
+            "{base_url}/api/marketdata/page/10?currency={}&endDate={}",
+            currency.iso_alpha_code,
+            date.format("%d-%m-%Y")
+        )
+    }
+
+    fn parse(
+        &self,
+        body: &[u8],
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        parse_response(&serde_json::from_slice(body)?, zone, currency)
+    }
+
+    fn parse_many(
+        &self,
+        body: &[u8],
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+    ) -> Result<ZonePrices, Error> {
+        let response = serde_json::from_slice(body)?;
+        zones
+            .iter()
+            .map(|&zone| Ok((zone, parse_response(&response, zone, currency)?)))
+            .collect()
+    }
+
+    fn parse_all(&self, body: &[u8], currency: &'static Currency) -> Result<ZonePrices, Error> {
+        let response = serde_json::from_slice(body)?;
+        published(|zone| parse_response(&response, zone, currency))
+    }
+}
+
+fn deserialize_partial_iso8601<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    NaiveDateTime::parse_from_str(&String::deserialize(deserializer)?, "%Y-%m-%dT%H:%M:%S")
+        .map_err(serde::de::Error::custom)
+}
+
+#[derive(Deserialize, Debug)]
+struct Response {
+    data: Data,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Data {
+    rows: Vec<Row>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Row {
+    #[serde(deserialize_with = "deserialize_partial_iso8601")]
+    start_time: NaiveDateTime,
+    columns: Vec<Column>,
+    is_extra_row: bool,
+}
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct Column {
+    name: String,
+    value: String,
+}
+
+/// Parses a price cell, which Nord Pool formats the Nordic way (`1 234,56`)
+/// regardless of currency.
+pub(crate) fn parse_price(value: &str, currency: &'static Currency) -> Option<Money> {
+    let normalized: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| if c == ',' { '.' } else { c })
+        .collect();
+    Decimal::from_str(&normalized)
+        .ok()
+        .map(|amount| Money::from_decimal(amount, currency))
+}
+
+pub(crate) fn is_placeholder(value: &str) -> bool {
+    matches!(value.trim(), "-" | "")
+}
+
+/// Maps a published local delivery start to an instant. Rows are in delivery
+/// order, so the repeated hour when daylight saving time ends resolves to the
+/// first instant after `previous`.
+pub(crate) fn resolve_local_time(
+    start_time: NaiveDateTime,
+    tz: Tz,
+    previous: Option<DateTime<Tz>>,
+) -> Result<DateTime<Tz>, Error> {
+    match start_time.and_local_timezone(tz) {
+        LocalResult::Single(t) => Ok(t),
+        LocalResult::Ambiguous(earliest, latest) => match previous {
+            Some(previous) if previous >= earliest => Ok(latest),
+            _ => Ok(earliest),
+        },
+        LocalResult::None => Err(Error::NonexistentLocalTime(start_time)),
+    }
+}
+
+fn parse_row(
+    row: &Row,
+    start_time: DateTime<Tz>,
+    zone: BiddingZone,
+    currency: &'static Currency,
+) -> Result<SpotPrice, Error> {
+    let column = row
+        .columns
+        .iter()
+        .find(|c| c.name == zone.column_name())
+        .ok_or_else(|| Error::UnknownArea(zone.name().to_string()))?;
+    if is_placeholder(&column.value) {
+        return Err(Error::MissingPrice(row.start_time));
+    }
+    let price = parse_price(&column.value, currency).ok_or_else(|| Error::UnparsablePrice {
+        start_time: row.start_time,
+        value: column.value.clone(),
+    })?;
+    Ok(SpotPrice {
+        start_time,
+        duration: Resolution::Hour.duration(),
+        price: price / 1000,
+    })
+}
+
+/// Parses the hourly spot prices per kWh of `zone` and the rows that could not
+/// be parsed.
+fn parse_response(
+    response: &Response,
+    zone: BiddingZone,
+    currency: &'static Currency,
+) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+    let rows: Vec<&Row> = response
+        .data
+        .rows
+        .iter()
+        .filter(|r| !r.is_extra_row)
+        .collect();
+    if !rows
+        .iter()
+        .any(|r| r.columns.iter().any(|c| c.name == zone.column_name()))
+    {
+        return Err(Error::UnknownArea(zone.name().to_string()));
+    }
+    let mut prices = Vec::with_capacity(rows.len());
+    let mut diagnostics = vec![];
+    let mut previous = None;
+    for row in rows {
+        let result =
+            resolve_local_time(row.start_time, zone.timezone(), previous).and_then(|start_time| {
+                previous = Some(start_time);
+                parse_row(row, start_time, zone, currency)
+            });
+        match result {
+            Ok(price) => prices.push(price),
+            // The hour skipped when daylight saving time starts is published
+            // without a price.
+            Err(Error::NonexistentLocalTime(_))
+                if row
+                    .columns
+                    .iter()
+                    .any(|c| c.name == zone.column_name() && is_placeholder(&c.value)) => {}
+            Err(error) => diagnostics.push(Diagnostic {
+                start_time: row.start_time,
+                error,
+            }),
+        }
+    }
+    Ok((prices, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::{EUR, SEK};
+
+    use super::Legacy;
+    use crate::{Backend, BiddingZone, Error};
+
+    const RESPONSE: &str = r#"{
+        "data": {
+            "Rows": [
+                {
+                    "StartTime": "2022-11-09T00:00:00",
+                    "IsExtraRow": false,
+                    "Columns": [{ "Name": "SE3", "Value": "1 234,56" }, { "Name": "Oslo", "Value": "2 000,00" }]
+                },
+                {
+                    "StartTime": "2022-11-09T01:00:00",
+                    "IsExtraRow": false,
+                    "Columns": [{ "Name": "SE3", "Value": "1 100,00" }, { "Name": "Oslo", "Value": "-" }]
+                },
+                {
+                    "StartTime": "2022-11-09T00:00:00",
+                    "IsExtraRow": true,
+                    "Columns": [{ "Name": "SE3", "Value": "1 167,28" }, { "Name": "Oslo", "Value": "2 000,00" }]
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn parse_response() {
+        let response = serde_json::from_str(RESPONSE).unwrap();
+        let (prices, _) = super::parse_response(&response, BiddingZone::SE3, SEK).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].price.amount(), &dec!(1.23456));
+        assert_eq!(
+            prices[1].start_time,
+            Stockholm.ymd(2022, 11, 9).and_hms(1, 0, 0)
+        );
+
+        let (prices, diagnostics) =
+            super::parse_response(&response, BiddingZone::NO1, SEK).unwrap();
+        assert_eq!(prices.len(), 1);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(diagnostics[0].error, Error::MissingPrice(_)));
+        let result = super::parse_response(&response, BiddingZone::FI, SEK);
+        assert!(matches!(result, Err(Error::UnknownArea(area)) if area == "FI"));
+
+        let prices = Legacy
+            .parse_many(
+                RESPONSE.as_bytes(),
+                &[BiddingZone::SE3, BiddingZone::NO1],
+                SEK,
+            )
+            .unwrap();
+        assert_eq!(prices[&BiddingZone::SE3].0.len(), 2);
+        assert_eq!(prices[&BiddingZone::NO1].1.len(), 1);
+
+        // Only the zones with a column are published.
+        let prices = Legacy.parse_all(RESPONSE.as_bytes(), SEK).unwrap();
+        let mut zones: Vec<_> = prices.keys().copied().collect();
+        zones.sort_by_key(|zone| zone.name());
+        assert_eq!(zones, [BiddingZone::NO1, BiddingZone::SE3]);
+    }
+
+    /// A response with an SE3 column for each `(local start, value)`.
+    fn response(rows: &[(NaiveDateTime, String)]) -> super::Response {
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|(start_time, value)| {
+                format!(
+                    r#"{{ "StartTime": "{}", "IsExtraRow": false, "Columns": [{{ "Name": "SE3", "Value": "{value}" }}] }}"#,
+                    start_time.format("%Y-%m-%dT%H:%M:%S")
+                )
+            })
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{ "data": {{ "Rows": [{}] }} }}"#,
+            rows.join(",")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn daylight_saving_ends() {
+        // The hour 02-03 is published twice on the last Sunday of October.
+        let date = NaiveDate::from_ymd(2022, 10, 30);
+        let rows: Vec<_> = (0..25)
+            .map(|i| {
+                let hour = if i <= 2 { i } else { i - 1 };
+                (date.and_hms(hour, 0, 0), format!("{i},00"))
+            })
+            .collect();
+        let (prices, diagnostics) =
+            super::parse_response(&response(&rows), BiddingZone::SE3, SEK).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(prices.len(), 25);
+        assert_eq!(
+            prices[2].start_time.to_rfc3339(),
+            "2022-10-30T02:00:00+02:00"
+        );
+        assert_eq!(
+            prices[3].start_time.to_rfc3339(),
+            "2022-10-30T02:00:00+01:00"
+        );
+        assert_eq!(prices[3].price.amount(), &dec!(0.003));
+        for pair in prices.windows(2) {
+            assert_eq!(pair[0].end_time(), pair[1].start_time);
+        }
+    }
+
+    #[test]
+    fn daylight_saving_starts() {
+        // The hour 02-03 does not exist on the last Sunday of March and is
+        // published without a price.
+        let date = NaiveDate::from_ymd(2023, 3, 26);
+        let rows: Vec<_> = (0..24)
+            .map(|h| {
+                let value = if h == 2 {
+                    "-".to_string()
+                } else {
+                    format!("{h},00")
+                };
+                (date.and_hms(h, 0, 0), value)
+            })
+            .collect();
+        let (prices, diagnostics) =
+            super::parse_response(&response(&rows), BiddingZone::SE3, SEK).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(prices.len(), 23);
+        assert_eq!(prices[1].end_time(), prices[2].start_time);
+        assert_eq!(
+            prices[2].start_time.to_rfc3339(),
+            "2023-03-26T03:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn parse_price() {
+        let price = super::parse_price("1 234,56", EUR).unwrap();
+        assert_eq!(price.amount(), &dec!(1234.56));
+        assert_eq!(price.currency(), EUR);
+    }
+}
@@ -0,0 +1,794 @@
+//! An on-disk cache of spot prices, one JSON file per source, zone, currency
+//! and delivery day.
+//!
+//! Day-ahead prices do not change once published, so final days are never
+//! refetched. A day is final once it is complete, or once it has passed and
+//! its only gaps are hours published without a price. Other days are only
+//! cached for the time to live, if one is set.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration as Ttl;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use rusty_money::iso::Currency;
+use serde::{Deserialize, Serialize};
+
+use crate::series::start_of_day;
+use crate::{BiddingZone, Diagnostic, Error, Money, PriceSource, SpotPrice, ZonePrices};
+
+type DayPrices = (Vec<SpotPrice>, Vec<Diagnostic>);
+
+/// Hits and misses of a [`CachedSource`] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Fetched days that could not be written to the cache, e.g. because
+    /// the disk is full. Their prices are still returned.
+    pub write_errors: u64,
+}
+
+/// Serves prices from a cache directory, fetching days that are not cached
+/// from another source.
+pub struct CachedSource<S> {
+    name: String,
+    source: S,
+    dir: PathBuf,
+    cache_only: bool,
+    ttl: Option<Ttl>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    write_errors: AtomicU64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Day {
+    prices: Vec<Price>,
+    diagnostics: Vec<Problem>,
+    /// When a day that was not final was fetched, `None` for final days.
+    fetched_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Price {
+    start_time: DateTime<Utc>,
+    minutes: i64,
+    amount: Decimal,
+}
+
+/// A cached [`Diagnostic`], by the local start time of the interval.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Problem {
+    MissingPrice {
+        start_time: NaiveDateTime,
+    },
+    UnparsablePrice {
+        start_time: NaiveDateTime,
+        value: String,
+    },
+    NonexistentLocalTime {
+        start_time: NaiveDateTime,
+    },
+}
+
+impl Problem {
+    /// `None` for errors that are not about a single interval.
+    fn new(diagnostic: &Diagnostic) -> Option<Self> {
+        let start_time = diagnostic.start_time;
+        match &diagnostic.error {
+            Error::MissingPrice(_) => Some(Problem::MissingPrice { start_time }),
+            Error::UnparsablePrice { value, .. } => Some(Problem::UnparsablePrice {
+                start_time,
+                value: value.clone(),
+            }),
+            Error::NonexistentLocalTime(_) => Some(Problem::NonexistentLocalTime { start_time }),
+            _ => None,
+        }
+    }
+
+    fn into_diagnostic(self) -> Diagnostic {
+        let (start_time, error) = match self {
+            Problem::MissingPrice { start_time } => (start_time, Error::MissingPrice(start_time)),
+            Problem::UnparsablePrice { start_time, value } => {
+                (start_time, Error::UnparsablePrice { start_time, value })
+            }
+            Problem::NonexistentLocalTime { start_time } => {
+                (start_time, Error::NonexistentLocalTime(start_time))
+            }
+        };
+        Diagnostic { start_time, error }
+    }
+}
+
+impl<S: PriceSource> CachedSource<S> {
+    /// Caches the prices of `source` in `dir`. `name` tells the files of
+    /// different sources apart, e.g. `nordpool` or `entsoe`.
+    pub fn new(name: impl Into<String>, source: S, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            dir: dir.into(),
+            cache_only: false,
+            ttl: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Only serves cached days and fails with [`Error::NotCached`] for
+    /// others, e.g. when offline.
+    pub fn cache_only(mut self, cache_only: bool) -> Self {
+        self.cache_only = cache_only;
+        self
+    }
+
+    /// Also caches days that are not final for `ttl`, e.g. to not ask for
+    /// tomorrow's prices on every call before they are published. Without
+    /// a time to live such days are fetched every time.
+    pub fn ttl(mut self, ttl: Ttl) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            write_errors: self.write_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    fn path(&self, zone: BiddingZone, currency: &'static Currency, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(&self.name)
+            .join(zone.name())
+            .join(currency.iso_alpha_code)
+            .join(format!("{}.json", date.format("%Y-%m-%d")))
+    }
+
+    /// Path of the list of zones published in a response of
+    /// [`PriceSource::spot_prices_all`].
+    fn published_path(&self, currency: &'static Currency, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(&self.name)
+            .join("published")
+            .join(currency.iso_alpha_code)
+            .join(format!("{}.json", date.format("%Y-%m-%d")))
+    }
+
+    /// The cached prices of the day, counted as a hit or a miss. Unreadable
+    /// files and expired days are treated as misses so that they are fetched
+    /// again, except that expired days are served in cache-only mode.
+    fn read(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<Option<DayPrices>, Error> {
+        let Some(prices) = self.load(zone, currency, date) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            if self.cache_only {
+                return Err(Error::NotCached(date));
+            }
+            return Ok(None);
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Ok(Some(prices))
+    }
+
+    /// Like [`CachedSource::read`], without counting.
+    fn load(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Option<DayPrices> {
+        let day: Day = fs::read(self.path(zone, currency, date))
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())?;
+        if !self.cache_only && self.is_expired(&day) {
+            return None;
+        }
+        let tz = zone.timezone();
+        let prices = day
+            .prices
+            .into_iter()
+            .map(|p| SpotPrice {
+                start_time: p.start_time.with_timezone(&tz),
+                duration: Duration::minutes(p.minutes),
+                price: Money::from_decimal(p.amount, currency),
+            })
+            .collect();
+        let diagnostics = day
+            .diagnostics
+            .into_iter()
+            .map(Problem::into_diagnostic)
+            .collect();
+        Some((prices, diagnostics))
+    }
+
+    /// The zones of the last response of the source with all zones, if it
+    /// was cached.
+    fn published(&self, currency: &'static Currency, date: NaiveDate) -> Option<Vec<BiddingZone>> {
+        let contents = fs::read(self.published_path(currency, date)).ok()?;
+        let zones: Vec<String> = serde_json::from_slice(&contents).ok()?;
+        zones
+            .iter()
+            .map(|zone| BiddingZone::from_str(zone).ok())
+            .collect()
+    }
+
+    fn is_expired(&self, day: &Day) -> bool {
+        let Some(fetched_at) = day.fetched_at else {
+            return false;
+        };
+        let age = (Utc::now() - fetched_at).to_std().unwrap_or_default();
+        self.ttl.is_none_or(|ttl| age >= ttl)
+    }
+
+    /// Caches the fetched prices of the day if they are final, or for the
+    /// time to live, and returns whether they were. Empty days are not
+    /// cached. Failures are counted in [`CacheStats::write_errors`].
+    fn write(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+        prices: &[SpotPrice],
+        diagnostics: &[Diagnostic],
+    ) -> bool {
+        match self.try_write(zone, currency, date, prices, diagnostics) {
+            Ok(written) => written,
+            Err(_) => {
+                self.write_errors.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    fn try_write(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+        prices: &[SpotPrice],
+        diagnostics: &[Diagnostic],
+    ) -> Result<bool, Error> {
+        let fetched_at = if is_final(zone, date, prices, diagnostics) {
+            None
+        } else if self.ttl.is_some() && !prices.is_empty() {
+            Some(Utc::now())
+        } else {
+            return Ok(false);
+        };
+        let Some(diagnostics) = diagnostics.iter().map(Problem::new).collect() else {
+            return Ok(false);
+        };
+        let day = Day {
+            prices: prices
+                .iter()
+                .map(|p| Price {
+                    start_time: p.start_time.with_timezone(&Utc),
+                    minutes: p.duration.num_minutes(),
+                    amount: *p.price.amount(),
+                })
+                .collect(),
+            diagnostics,
+            fetched_at,
+        };
+        write_file(&self.path(zone, currency, date), &serde_json::to_vec(&day)?)?;
+        Ok(true)
+    }
+
+    /// Records the zones of a response with all zones, counting failures in
+    /// [`CacheStats::write_errors`].
+    fn write_published(
+        &self,
+        currency: &'static Currency,
+        date: NaiveDate,
+        zones: impl Iterator<Item = BiddingZone>,
+    ) {
+        let names: Vec<_> = zones.map(|zone| zone.name()).collect();
+        let result = serde_json::to_vec(&names)
+            .map_err(Error::from)
+            .and_then(|contents| write_file(&self.published_path(currency, date), &contents));
+        if result.is_err() {
+            self.write_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(Error::Cache)?;
+    }
+    // Write to a temporary file first so that readers never see a partial
+    // file.
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, contents).map_err(Error::Cache)?;
+    fs::rename(&temporary, path).map_err(Error::Cache)
+}
+
+/// Whether the prices of the day will not change: the prices cover the
+/// whole day without gaps, or the day has passed and its only gaps are
+/// intervals published without a price.
+pub(crate) fn is_final(
+    zone: BiddingZone,
+    date: NaiveDate,
+    prices: &[SpotPrice],
+    diagnostics: &[Diagnostic],
+) -> bool {
+    let tz = zone.timezone();
+    // Prices of days that have not passed may still be published.
+    let past = date < Utc::now().with_timezone(&tz).date_naive();
+    let missing: Option<Vec<_>> = diagnostics
+        .iter()
+        .map(|d| match d.error {
+            Error::MissingPrice(_) if past => Some(d.start_time),
+            _ => None,
+        })
+        .collect();
+    missing.is_some_and(|missing| covers_day(tz, date, prices, &missing))
+}
+
+/// Whether `prices` and the intervals starting at the local times `missing`
+/// cover the day without gaps or overlaps.
+fn covers_day(tz: Tz, date: NaiveDate, prices: &[SpotPrice], missing: &[NaiveDateTime]) -> bool {
+    let Some(step) = prices.first().map(|p| p.duration) else {
+        return false;
+    };
+    let mut intervals: Vec<_> = prices.iter().map(|p| (p.start_time, p.duration)).collect();
+    for start_time in missing {
+        let start_time = match tz.from_local_datetime(start_time) {
+            LocalResult::Single(t) => t,
+            // The hour repeated when daylight saving time ends.
+            LocalResult::Ambiguous(first, second) => {
+                if intervals.iter().any(|&(t, _)| t == first) {
+                    second
+                } else {
+                    first
+                }
+            }
+            LocalResult::None => return false,
+        };
+        intervals.push((start_time, step));
+    }
+    intervals.sort_by_key(|&(start_time, _)| start_time);
+    let mut end = start_of_day(tz, date);
+    for (start_time, duration) in intervals {
+        if start_time != end {
+            return false;
+        }
+        end = start_time + duration;
+    }
+    end == start_of_day(tz, date.succ())
+}
+
+#[async_trait]
+impl<S: PriceSource> PriceSource for CachedSource<S> {
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<DayPrices, Error> {
+        if let Some(cached) = self.read(zone, currency, date)? {
+            return Ok(cached);
+        }
+        let (prices, diagnostics) = self.source.spot_prices(zone, currency, date).await?;
+        self.write(zone, currency, date, &prices, &diagnostics);
+        Ok((prices, diagnostics))
+    }
+
+    /// Fetches the zones that are not cached with a single call to the
+    /// source.
+    async fn spot_prices_many(
+        &self,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let mut prices = ZonePrices::with_capacity(zones.len());
+        let mut missing = vec![];
+        for &zone in zones {
+            match self.read(zone, currency, date)? {
+                Some(cached) => {
+                    prices.insert(zone, cached);
+                }
+                None => missing.push(zone),
+            }
+        }
+        if !missing.is_empty() {
+            let fetched = self
+                .source
+                .spot_prices_many(&missing, currency, date)
+                .await?;
+            for (zone, (zone_prices, diagnostics)) in fetched {
+                self.write(zone, currency, date, &zone_prices, &diagnostics);
+                prices.insert(zone, (zone_prices, diagnostics));
+            }
+        }
+        Ok(prices)
+    }
+
+    /// Serves every zone from the cache once all zones of a response of the
+    /// source are cached, and otherwise fetches all zones with a single call
+    /// to the source. In cache-only mode the cached zones are served.
+    async fn spot_prices_all(
+        &self,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let published = self.published(currency, date);
+        let zones = published.as_deref().unwrap_or(&BiddingZone::ALL);
+        let cached: ZonePrices = zones
+            .iter()
+            .filter_map(|&zone| Some((zone, self.load(zone, currency, date)?)))
+            .collect();
+        let complete = published.is_some() && cached.len() == zones.len();
+        if complete || (self.cache_only && !cached.is_empty()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(cached);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        if self.cache_only {
+            return Err(Error::NotCached(date));
+        }
+        let fetched = self.source.spot_prices_all(currency, date).await?;
+        let mut written = true;
+        for (&zone, (prices, diagnostics)) in &fetched {
+            written &= self.write(zone, currency, date, prices, diagnostics);
+        }
+        if written && !fetched.is_empty() {
+            self.write_published(currency, date, fetched.keys().copied());
+        }
+        Ok(fetched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Europe::Stockholm;
+    use rusty_money::iso::{Currency, SEK};
+
+    use super::{CacheStats, CachedSource, DayPrices};
+    use crate::mock::hours;
+    use crate::{BiddingZone, Diagnostic, Error, MemorySource, PriceSource};
+
+    /// Publishes 23 hours of a day and a diagnostic for the last one.
+    struct Flawed {
+        error: fn(chrono::NaiveDateTime) -> Error,
+        calls: AtomicUsize,
+    }
+
+    impl Flawed {
+        fn new(error: fn(chrono::NaiveDateTime) -> Error) -> Self {
+            Self {
+                error,
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PriceSource for Flawed {
+        async fn spot_prices(
+            &self,
+            _zone: BiddingZone,
+            _currency: &'static Currency,
+            date: NaiveDate,
+        ) -> Result<DayPrices, Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let start_time = date.and_hms(23, 0, 0);
+            let diagnostic = Diagnostic {
+                start_time,
+                error: (self.error)(start_time),
+            };
+            let date = Stockholm.from_local_date(&date).unwrap();
+            Ok((hours(date, 0..23), vec![diagnostic]))
+        }
+    }
+
+    /// The diagnostic of the second of two requests of `date`.
+    async fn fetch_twice<S: PriceSource>(cache: &CachedSource<S>, date: NaiveDate) -> Diagnostic {
+        let mut diagnostics = vec![];
+        for _ in 0..2 {
+            diagnostics = cache
+                .spot_prices(BiddingZone::SE3, SEK, date)
+                .await
+                .unwrap()
+                .1;
+        }
+        diagnostics.pop().unwrap()
+    }
+
+    #[tokio::test]
+    async fn caches_final_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let past = Stockholm.ymd(2022, 11, 9);
+        let future = Stockholm.ymd(2099, 11, 9);
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, past.naive_local(), hours(past, 0..24));
+        source.insert(BiddingZone::SE3, future.naive_local(), hours(future, 0..12));
+        let cache = CachedSource::new("memory", source, dir.path());
+
+        let fetched = cache
+            .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+            .await
+            .unwrap();
+        let cached = cache
+            .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(cached.0, fetched.0);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                write_errors: 0
+            }
+        );
+        assert!(dir.path().join("memory/SE3/SEK/2022-11-09.json").is_file());
+
+        // Half of a day that has not passed yet is not cached.
+        for _ in 0..2 {
+            cache
+                .spot_prices(BiddingZone::SE3, SEK, future.naive_local())
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                write_errors: 0
+            }
+        );
+
+        let offline = CachedSource::new("memory", MemorySource::new(), dir.path()).cache_only(true);
+        let result = offline
+            .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+            .await;
+        assert_eq!(result.unwrap().0, fetched.0);
+        let result = offline
+            .spot_prices(BiddingZone::SE3, SEK, future.naive_local())
+            .await;
+        assert!(matches!(result, Err(Error::NotCached(_))));
+    }
+
+    #[tokio::test]
+    async fn caches_zones_fetched_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let date = Stockholm.ymd(2022, 11, 9);
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, date.naive_local(), hours(date, 0..24));
+        source.insert(BiddingZone::SE4, date.naive_local(), hours(date, 0..24));
+        let cache = CachedSource::new("memory", source, dir.path());
+
+        cache
+            .spot_prices(BiddingZone::SE3, SEK, date.naive_local())
+            .await
+            .unwrap();
+        let prices = cache
+            .spot_prices_many(
+                &[BiddingZone::SE3, BiddingZone::SE4],
+                SEK,
+                date.naive_local(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                write_errors: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_past_gaps_and_expires_other_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let past = NaiveDate::from_ymd(2022, 11, 9);
+        let unparsable = |start_time| Error::UnparsablePrice {
+            start_time,
+            value: "n/a".to_string(),
+        };
+
+        // Hours published without a price are final once the day has passed.
+        let cache = CachedSource::new("missing", Flawed::new(Error::MissingPrice), dir.path());
+        let diagnostic = fetch_twice(&cache, past).await;
+        assert!(matches!(diagnostic.error, Error::MissingPrice(_)));
+        assert_eq!(cache.source.calls.load(Ordering::Relaxed), 1);
+
+        // Parse errors are not, but are cached with their kind for the
+        // time to live.
+        let cache = CachedSource::new("unparsable", Flawed::new(unparsable), dir.path());
+        for _ in 0..2 {
+            cache
+                .spot_prices(BiddingZone::SE3, SEK, past)
+                .await
+                .unwrap();
+        }
+        assert_eq!(cache.source.calls.load(Ordering::Relaxed), 2);
+        let cache = cache.ttl(Duration::from_secs(3600));
+        let diagnostic = fetch_twice(&cache, past).await;
+        assert!(matches!(
+            diagnostic.error,
+            Error::UnparsablePrice { value, .. } if value == "n/a"
+        ));
+        assert_eq!(cache.source.calls.load(Ordering::Relaxed), 3);
+
+        let cache = CachedSource::new("unparsable", Flawed::new(unparsable), dir.path())
+            .ttl(Duration::ZERO);
+        cache
+            .spot_prices(BiddingZone::SE3, SEK, past)
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 1,
+                write_errors: 0
+            }
+        );
+
+        // Empty days are never cached.
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, past, vec![]);
+        let cache = CachedSource::new("empty", source, dir.path()).ttl(Duration::from_secs(3600));
+        cache
+            .spot_prices(BiddingZone::SE3, SEK, past)
+            .await
+            .unwrap();
+        assert!(!dir.path().join("empty/SE3/SEK/2022-11-09.json").exists());
+    }
+
+    #[tokio::test]
+    async fn partial_past_days_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let past = Stockholm.ymd(2022, 11, 9);
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, past.naive_local(), hours(past, 0..12));
+
+        // Without a time to live the half day is fetched every time.
+        let cache = CachedSource::new("memory", source.clone(), dir.path());
+        for _ in 0..2 {
+            cache
+                .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 2,
+                write_errors: 0
+            }
+        );
+
+        let cache = cache.ttl(Duration::from_secs(3600));
+        for _ in 0..2 {
+            cache
+                .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                write_errors: 0
+            }
+        );
+
+        // Once expired it is fetched again.
+        let cache = CachedSource::new("memory", source, dir.path()).ttl(Duration::ZERO);
+        let (prices, _) = cache
+            .spot_prices(BiddingZone::SE3, SEK, past.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 12);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 1,
+                write_errors: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn write_errors_keep_fetched_prices() {
+        // A file where the cache directory should be.
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let date = Stockholm.ymd(2022, 11, 9);
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, date.naive_local(), hours(date, 0..24));
+        let cache = CachedSource::new("memory", source, file.path());
+
+        let (prices, _) = cache
+            .spot_prices(BiddingZone::SE3, SEK, date.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 24);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 0,
+                misses: 1,
+                write_errors: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_all_zones() {
+        let dir = tempfile::tempdir().unwrap();
+        let date = Stockholm.ymd(2022, 11, 9);
+        let next = date.succ();
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, date.naive_local(), hours(date, 0..24));
+        source.insert(BiddingZone::SE4, date.naive_local(), hours(date, 0..24));
+        source.insert(BiddingZone::SE3, next.naive_local(), hours(next, 0..24));
+        let cache = CachedSource::new("memory", source, dir.path());
+
+        for _ in 0..2 {
+            let prices = cache
+                .spot_prices_all(SEK, date.naive_local())
+                .await
+                .unwrap();
+            assert_eq!(prices.len(), 2);
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                write_errors: 0
+            }
+        );
+
+        // Offline, zones cached one at a time are served too.
+        cache
+            .spot_prices(BiddingZone::SE3, SEK, next.naive_local())
+            .await
+            .unwrap();
+        let offline = CachedSource::new("memory", MemorySource::new(), dir.path()).cache_only(true);
+        let prices = offline
+            .spot_prices_all(SEK, date.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 2);
+        let prices = offline
+            .spot_prices_all(SEK, next.naive_local())
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 1);
+        let result = offline
+            .spot_prices_all(SEK, next.succ().naive_local())
+            .await;
+        assert!(matches!(result, Err(Error::NotCached(_))));
+    }
+}
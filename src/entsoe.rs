@@ -0,0 +1,422 @@
+//! Day-ahead prices from the ENTSO-E Transparency Platform, a fallback for
+//! when Nord Pool is unavailable.
+//!
+//! Prices are requested as Energy Prices documents (type A44) and are only
+//! published in EUR per MWh.
+
+use std::str::FromStr;
+use std::time::Duration as Timeout;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Europe;
+use roxmltree::{Document, Node};
+use rust_decimal::Decimal;
+use rusty_money::iso::{self, Currency};
+
+use crate::backend::USER_AGENT;
+use crate::{BiddingZone, Diagnostic, Error, Money, PriceSource, RetryPolicy, SpotPrice};
+
+const ENTSOE_URL: &str = "https://web-api.tp.entsoe.eu/api";
+
+/// Fetches day-ahead prices from the ENTSO-E Transparency Platform REST API.
+///
+/// Like [`NordPoolClient`](crate::NordPoolClient), the client keeps one
+/// connection pool for all requests, so create it once and reuse it.
+///
+/// ENTSO-E only publishes prices in EUR, and requests in any other currency
+/// fail with [`Error::UnsupportedCurrency`]. Use it with a tariff in EUR.
+pub struct EntsoeClient {
+    token: String,
+    base_url: String,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+}
+
+impl EntsoeClient {
+    /// A client authenticating with the platform security token `token`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self::builder(token).build().expect("default HTTP client")
+    }
+
+    pub fn builder(token: impl Into<String>) -> EntsoeClientBuilder {
+        EntsoeClientBuilder {
+            token: token.into(),
+            base_url: None,
+            client: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: USER_AGENT.to_string(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    fn url(&self, eic: &str, date: NaiveDate) -> Result<String, Error> {
+        // Delivery days are CET days, like at Nord Pool.
+        let midnight = |date: NaiveDate| {
+            Europe::Brussels
+                .from_local_datetime(&date.and_hms(0, 0, 0))
+                .unwrap()
+                .with_timezone(&Utc)
+                .format("%Y%m%d%H%M")
+                .to_string()
+        };
+        let request = self
+            .http
+            .get(&self.base_url)
+            .query(&[
+                ("securityToken", self.token.as_str()),
+                ("documentType", "A44"),
+                ("in_Domain", eic),
+                ("out_Domain", eic),
+                ("periodStart", &midnight(date)),
+                ("periodEnd", &midnight(date.succ())),
+            ])
+            .build()?;
+        Ok(request.url().to_string())
+    }
+}
+
+#[async_trait]
+impl PriceSource for EntsoeClient {
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        let eic = zone
+            .eic()
+            .ok_or_else(|| Error::UnknownArea(zone.name().to_string()))?;
+        if currency != iso::EUR {
+            return Err(Error::UnsupportedCurrency(currency.iso_alpha_code));
+        }
+        let url = self.url(eic, date).map_err(without_url)?;
+        let (_, body) = self
+            .retry
+            .get(&self.http, &url)
+            .await
+            .map_err(without_url)?;
+        let xml = String::from_utf8(body).map_err(|e| Error::Document(e.to_string()))?;
+        parse_document(&xml, zone, currency, date)
+    }
+}
+
+/// Removes the request URL, which contains the security token, from
+/// transport errors.
+fn without_url(error: Error) -> Error {
+    match error {
+        Error::Transport(e) => Error::Transport(e.without_url()),
+        Error::RetriesExhausted { attempts, last } => Error::RetriesExhausted {
+            attempts,
+            last: Box::new(without_url(*last)),
+        },
+        error => error,
+    }
+}
+
+/// Configures an [`EntsoeClient`].
+///
+/// The HTTP options are ignored when an existing [`reqwest::Client`] is
+/// passed with [`EntsoeClientBuilder::client`].
+pub struct EntsoeClientBuilder {
+    token: String,
+    base_url: Option<String>,
+    client: Option<reqwest::Client>,
+    timeout: Option<Timeout>,
+    connect_timeout: Option<Timeout>,
+    user_agent: String,
+    retry: RetryPolicy,
+}
+
+impl EntsoeClientBuilder {
+    /// Sends requests to `base_url` instead of the public API, e.g. a local
+    /// mock server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sends requests with `client`, e.g. to share a connection pool with a
+    /// [`NordPoolClient`](crate::NordPoolClient).
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Timeout of each request, from connecting until the body is read.
+    pub fn timeout(mut self, timeout: Timeout) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Timeout) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The `User-Agent` header, `nordpool/<version>` by default.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// How failed requests are retried, [`RetryPolicy::default`] unless set.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fails with [`Error::Transport`] if the HTTP client cannot be built.
+    pub fn build(self) -> Result<EntsoeClient, Error> {
+        let http = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = reqwest::Client::builder().user_agent(self.user_agent);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+        let base_url = self
+            .base_url
+            .as_deref()
+            .unwrap_or(ENTSOE_URL)
+            .trim_end_matches('/')
+            .to_string();
+        Ok(EntsoeClient {
+            token: self.token,
+            base_url,
+            http,
+            retry: self.retry,
+        })
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, Error> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .ok_or_else(|| Error::Document(format!("missing {name} in {}", node.tag_name().name())))
+}
+
+/// Parses a `2024-10-26T22:00Z` timestamp.
+fn parse_time(value: &str) -> Result<DateTime<Utc>, Error> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%MZ")
+        .map(|t| Utc.from_utc_datetime(&t))
+        .map_err(|_| Error::Document(format!("invalid time '{value}'")))
+}
+
+fn parse_resolution(value: &str) -> Result<Duration, Error> {
+    value
+        .strip_prefix("PT")
+        .and_then(|v| v.strip_suffix('M'))
+        .and_then(|minutes| minutes.parse().ok())
+        .map(Duration::minutes)
+        .ok_or_else(|| Error::Document(format!("unsupported resolution '{value}'")))
+}
+
+/// Parses an A44 document into the spot prices per kWh of `zone` in
+/// `currency` and the points that could not be parsed.
+///
+/// Points may be left out when the price does not change (curve type A03),
+/// so each position takes the price of the last point at or before it. When
+/// the same interval is published in several resolutions, the finest is
+/// kept.
+fn parse_document(
+    xml: &str,
+    zone: BiddingZone,
+    currency: &'static Currency,
+    date: NaiveDate,
+) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+    let document = Document::parse(xml).map_err(|e| Error::Document(e.to_string()))?;
+    let root = document.root_element();
+    if root.has_tag_name("Acknowledgement_MarketDocument") {
+        let reason = child(root, "Reason")
+            .map(|reason| text(reason, "text"))
+            .transpose()?
+            .unwrap_or_default();
+        return Err(if reason.starts_with("No matching data found") {
+            Error::NotPublished(date)
+        } else {
+            Error::Document(reason.to_string())
+        });
+    }
+    if !root.has_tag_name("Publication_MarketDocument") {
+        return Err(Error::Document(format!(
+            "unexpected {}",
+            root.tag_name().name()
+        )));
+    }
+
+    let eic = zone.eic();
+    let tz = zone.timezone();
+    let mut prices: Vec<SpotPrice> = vec![];
+    let mut diagnostics = vec![];
+    for series in root.children().filter(|n| n.has_tag_name("TimeSeries")) {
+        if Some(text(series, "in_Domain.mRID")?) != eic {
+            continue;
+        }
+        if text(series, "currency_Unit.name")? != currency.iso_alpha_code {
+            return Err(Error::UnsupportedCurrency(currency.iso_alpha_code));
+        }
+        for period in series.children().filter(|n| n.has_tag_name("Period")) {
+            let interval = child(period, "timeInterval")
+                .ok_or_else(|| Error::Document("missing timeInterval in Period".to_string()))?;
+            let start = parse_time(text(interval, "start")?)?;
+            let end = parse_time(text(interval, "end")?)?;
+            let resolution = parse_resolution(text(period, "resolution")?)?;
+            let mut points: Vec<(i64, &str)> = period
+                .children()
+                .filter(|n| n.has_tag_name("Point"))
+                .map(|point| {
+                    let position = text(point, "position")?;
+                    let position = position
+                        .parse()
+                        .map_err(|_| Error::Document(format!("invalid position '{position}'")))?;
+                    Ok((position, text(point, "price.amount")?))
+                })
+                .collect::<Result<_, Error>>()?;
+            points.sort_by_key(|(position, _)| *position);
+
+            let count = (end - start).num_seconds() / resolution.num_seconds();
+            for position in 1..=count {
+                let i = points.partition_point(|(p, _)| *p <= position);
+                let start_time = (start + resolution * (position - 1) as i32).with_timezone(&tz);
+                let Some((_, value)) = i.checked_sub(1).map(|i| points[i]) else {
+                    diagnostics.push(Diagnostic {
+                        start_time: start_time.naive_local(),
+                        error: Error::MissingPrice(start_time.naive_local()),
+                    });
+                    continue;
+                };
+                match Decimal::from_str(value) {
+                    Ok(amount) => prices.push(SpotPrice {
+                        start_time,
+                        duration: resolution,
+                        price: Money::from_decimal(amount, currency) / 1000,
+                    }),
+                    Err(_) => diagnostics.push(Diagnostic {
+                        start_time: start_time.naive_local(),
+                        error: Error::UnparsablePrice {
+                            start_time: start_time.naive_local(),
+                            value: value.to_string(),
+                        },
+                    }),
+                }
+            }
+        }
+    }
+    if prices.is_empty() && diagnostics.is_empty() {
+        return Err(Error::UnknownArea(zone.name().to_string()));
+    }
+    prices.sort_by_key(|p| (p.start_time, p.duration));
+    prices.dedup_by_key(|p| p.start_time);
+    Ok((prices, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::{EUR, SEK};
+
+    use super::{parse_document, EntsoeClient};
+    use crate::mock::MockServer;
+    use crate::{BiddingZone, Error, PriceSource, RetryPolicy};
+
+    const DAYLIGHT_SAVING_ENDS: &str = include_str!("../fixtures/entsoe-2024-10-27-SE3.xml");
+    const NO_DATA: &str = include_str!("../fixtures/entsoe-no-data.xml");
+
+    #[test]
+    fn parse_a44() {
+        let date = NaiveDate::from_ymd(2024, 10, 27);
+        let (prices, diagnostics) =
+            parse_document(DAYLIGHT_SAVING_ENDS, BiddingZone::SE3, EUR, date).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(prices.len(), 25);
+        assert_eq!(
+            prices[0].start_time.to_rfc3339(),
+            "2024-10-27T00:00:00+02:00"
+        );
+        assert_eq!(prices[0].price.amount(), &dec!(0.02748));
+        assert_eq!(prices[0].price.currency(), EUR);
+        // Position 4 is left out and repeats the price of position 3.
+        assert_eq!(prices[3].price, prices[2].price);
+        assert_eq!(
+            prices[3].start_time.to_rfc3339(),
+            "2024-10-27T02:00:00+01:00"
+        );
+        assert!(prices.iter().all(|p| p.duration == Duration::hours(1)));
+        for pair in prices.windows(2) {
+            assert_eq!(pair[0].end_time(), pair[1].start_time);
+        }
+
+        let result = parse_document(DAYLIGHT_SAVING_ENDS, BiddingZone::SE4, EUR, date);
+        assert!(matches!(result, Err(Error::UnknownArea(area)) if area == "SE4"));
+        let result = parse_document(NO_DATA, BiddingZone::SE3, EUR, date);
+        assert!(matches!(result, Err(Error::NotPublished(d)) if d == date));
+        let result = parse_document(DAYLIGHT_SAVING_ENDS, BiddingZone::SE3, SEK, date);
+        assert!(matches!(result, Err(Error::UnsupportedCurrency("SEK"))));
+    }
+
+    #[test]
+    fn request_url() {
+        let url = EntsoeClient::new("a&b=c")
+            .url(
+                BiddingZone::SE3.eic().unwrap(),
+                NaiveDate::from_ymd(2024, 10, 27),
+            )
+            .unwrap();
+        assert!(url.contains("?securityToken=a%26b%3Dc&documentType=A44&"));
+        assert!(url.ends_with("&periodStart=202410262200&periodEnd=202410272300"));
+    }
+
+    #[tokio::test]
+    async fn retries_and_hides_token() {
+        let date = NaiveDate::from_ymd(2024, 10, 27);
+        let server = MockServer::start(vec![
+            ("503 Service Unavailable", ""),
+            ("200 OK", DAYLIGHT_SAVING_ENDS),
+        ])
+        .await;
+        let client = EntsoeClient::builder("secret-token")
+            .base_url(&server.url)
+            .retry(RetryPolicy {
+                initial_backoff: std::time::Duration::from_millis(1),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let (prices, _) = client
+            .spot_prices(BiddingZone::SE3, EUR, date)
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 25);
+        assert_eq!(server.requests.lock().unwrap().len(), 2);
+
+        // Nothing listens on the port of a dropped listener.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let client = EntsoeClient::builder("secret-token")
+            .base_url(url)
+            .retry(RetryPolicy::none())
+            .build()
+            .unwrap();
+        let error = client
+            .spot_prices(BiddingZone::SE3, EUR, date)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Transport(_)));
+        assert!(!format!("{error} {error:?}").contains("secret-token"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use reqwest::StatusCode;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The HTTP request could not be sent or the response not be read, e.g.
+    /// a connection reset or timeout.
+    Transport(reqwest::Error),
+    /// The price source answered with a non-success status.
+    Status(StatusCode),
+    /// The response did not have the expected JSON layout.
+    Schema(serde_json::Error),
+    /// The response or file was not the expected document.
+    Document(String),
+    /// The prices of the delivery day have not been published yet.
+    NotPublished(NaiveDate),
+    /// A cache file could not be read or written.
+    Cache(std::io::Error),
+    /// The delivery day is not cached and the cache is in cache-only mode,
+    /// or the day is not in the price store.
+    NotCached(NaiveDate),
+    /// The price store database failed.
+    #[cfg(feature = "store")]
+    Store(rusqlite::Error),
+    /// The response has no prices for the requested area.
+    UnknownArea(String),
+    /// Nord Pool published no price for the hour, e.g. a `-` placeholder.
+    MissingPrice(NaiveDateTime),
+    /// A price cell could not be parsed.
+    UnparsablePrice {
+        start_time: NaiveDateTime,
+        value: String,
+    },
+    /// No price was published between two intervals of a range.
+    Gap {
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    },
+    /// A delivery hour does not exist in the zone's timezone.
+    NonexistentLocalTime(NaiveDateTime),
+    /// The request still failed after retrying, see
+    /// [`RetryPolicy`](crate::RetryPolicy).
+    RetriesExhausted { attempts: u32, last: Box<Error> },
+    /// The price source does not publish prices in the requested currency.
+    UnsupportedCurrency(&'static str),
+    /// The tariff is expressed in a different currency than the prices.
+    CurrencyMismatch {
+        tariff: &'static str,
+        prices: &'static str,
+    },
+}
+
+impl Error {
+    /// Whether the request may succeed if retried later, as opposed to
+    /// errors caused by the request or by changes to the API.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Transport(_) => true,
+            Error::Status(status) => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            Error::RetriesExhausted { last, .. } => last.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "request failed: {e}"),
+            Error::Status(status) => write!(f, "price source responded with {status}"),
+            Error::Schema(e) => write!(f, "unexpected response: {e}"),
+            Error::Document(message) => write!(f, "unexpected document: {message}"),
+            Error::NotPublished(date) => write!(f, "prices for {date} are not published yet"),
+            Error::Cache(e) => write!(f, "cache failed: {e}"),
+            Error::NotCached(date) => write!(f, "prices for {date} are not cached"),
+            #[cfg(feature = "store")]
+            Error::Store(e) => write!(f, "price store failed: {e}"),
+            Error::UnknownArea(area) => write!(f, "no prices for area '{area}' in response"),
+            Error::MissingPrice(t) => write!(f, "no price published for {t}"),
+            Error::UnparsablePrice { start_time, value } => {
+                write!(f, "unparsable price '{value}' at {start_time}")
+            }
+            Error::Gap { from, to } => write!(f, "no prices from {from} to {to}"),
+            Error::NonexistentLocalTime(t) => write!(f, "nonexistent local time {t}"),
+            Error::RetriesExhausted { attempts, .. } => {
+                write!(f, "gave up after {attempts} attempts")
+            }
+            Error::UnsupportedCurrency(code) => write!(
+                f,
+                "prices are not published in {code} (Nord Pool publishes {})",
+                crate::SUPPORTED_CURRENCIES
+                    .iter()
+                    .map(|c| c.iso_alpha_code)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Error::CurrencyMismatch { tariff, prices } => write!(
+                f,
+                "tariff is in {tariff} but prices were requested in {prices}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::Schema(e) => Some(e),
+            Error::Cache(e) => Some(e),
+            #[cfg(feature = "store")]
+            Error::Store(e) => Some(e),
+            Error::RetriesExhausted { last, .. } => Some(last.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Schema(e)
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Store(e)
+    }
+}
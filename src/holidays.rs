@@ -0,0 +1,224 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Deserialize;
+
+/// Public holiday calendars, computed for any year including movable feasts.
+///
+/// Days that are not official public holidays but are billed as such by grid
+/// operators, like Midsummer's Eve and Christmas Eve in Sweden, are included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum HolidayCalendar {
+    #[serde(rename = "SE")]
+    Sweden,
+    #[serde(rename = "NO")]
+    Norway,
+    #[serde(rename = "DK")]
+    Denmark,
+    #[serde(rename = "FI")]
+    Finland,
+    #[serde(rename = "EE")]
+    Estonia,
+    #[serde(rename = "LV")]
+    Latvia,
+    #[serde(rename = "LT")]
+    Lithuania,
+}
+
+/// Easter Sunday in the Gregorian calendar (anonymous Gregorian algorithm).
+pub fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd(year, month as u32, day as u32)
+}
+
+/// The first `weekday` on or after `year-month-day`.
+fn first_weekday_from(year: i32, month: u32, day: u32, weekday: Weekday) -> NaiveDate {
+    let date = NaiveDate::from_ymd(year, month, day);
+    let offset = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(offset.into())
+}
+
+impl HolidayCalendar {
+    /// The holidays of `year`, sorted by date.
+    pub fn holidays(&self, year: i32) -> Vec<(NaiveDate, &'static str)> {
+        let date = |month, day| NaiveDate::from_ymd(year, month, day);
+        let easter = easter(year);
+        let from_easter = |days| easter + Duration::days(days);
+        let mut holidays = match self {
+            HolidayCalendar::Sweden => vec![
+                (date(1, 1), "Nyårsdagen"),
+                (date(1, 6), "Trettondedag jul"),
+                (from_easter(-2), "Långfredagen"),
+                (easter, "Påskdagen"),
+                (from_easter(1), "Annandag påsk"),
+                (date(5, 1), "Första maj"),
+                (from_easter(39), "Kristi himmelsfärdsdag"),
+                (from_easter(49), "Pingstdagen"),
+                (date(6, 6), "Sveriges nationaldag"),
+                (
+                    first_weekday_from(year, 6, 19, Weekday::Fri),
+                    "Midsommarafton",
+                ),
+                (
+                    first_weekday_from(year, 6, 20, Weekday::Sat),
+                    "Midsommardagen",
+                ),
+                (
+                    first_weekday_from(year, 10, 31, Weekday::Sat),
+                    "Alla helgons dag",
+                ),
+                (date(12, 24), "Julafton"),
+                (date(12, 25), "Juldagen"),
+                (date(12, 26), "Annandag jul"),
+                (date(12, 31), "Nyårsafton"),
+            ],
+            HolidayCalendar::Norway => vec![
+                (date(1, 1), "Første nyttårsdag"),
+                (from_easter(-3), "Skjærtorsdag"),
+                (from_easter(-2), "Langfredag"),
+                (easter, "Første påskedag"),
+                (from_easter(1), "Andre påskedag"),
+                (date(5, 1), "Arbeidernes dag"),
+                (date(5, 17), "Grunnlovsdag"),
+                (from_easter(39), "Kristi himmelfartsdag"),
+                (from_easter(49), "Første pinsedag"),
+                (from_easter(50), "Andre pinsedag"),
+                (date(12, 25), "Første juledag"),
+                (date(12, 26), "Andre juledag"),
+            ],
+            HolidayCalendar::Denmark => {
+                let mut holidays = vec![
+                    (date(1, 1), "Nytårsdag"),
+                    (from_easter(-3), "Skærtorsdag"),
+                    (from_easter(-2), "Langfredag"),
+                    (easter, "Påskedag"),
+                    (from_easter(1), "Anden påskedag"),
+                    (from_easter(39), "Kristi himmelfartsdag"),
+                    (from_easter(49), "Pinsedag"),
+                    (from_easter(50), "Anden pinsedag"),
+                    (date(12, 25), "Juledag"),
+                    (date(12, 26), "Anden juledag"),
+                ];
+                // Abolished from 2024.
+                if year < 2024 {
+                    holidays.push((from_easter(26), "Store bededag"));
+                }
+                holidays
+            }
+            HolidayCalendar::Finland => vec![
+                (date(1, 1), "Uudenvuodenpäivä"),
+                (date(1, 6), "Loppiainen"),
+                (from_easter(-2), "Pitkäperjantai"),
+                (easter, "Pääsiäispäivä"),
+                (from_easter(1), "Toinen pääsiäispäivä"),
+                (date(5, 1), "Vappu"),
+                (from_easter(39), "Helatorstai"),
+                (from_easter(49), "Helluntaipäivä"),
+                (
+                    first_weekday_from(year, 6, 19, Weekday::Fri),
+                    "Juhannusaatto",
+                ),
+                (
+                    first_weekday_from(year, 6, 20, Weekday::Sat),
+                    "Juhannuspäivä",
+                ),
+                (
+                    first_weekday_from(year, 10, 31, Weekday::Sat),
+                    "Pyhäinpäivä",
+                ),
+                (date(12, 6), "Itsenäisyyspäivä"),
+                (date(12, 24), "Jouluaatto"),
+                (date(12, 25), "Joulupäivä"),
+                (date(12, 26), "Tapaninpäivä"),
+            ],
+            HolidayCalendar::Estonia => vec![
+                (date(1, 1), "Uusaasta"),
+                (date(2, 24), "Iseseisvuspäev"),
+                (from_easter(-2), "Suur reede"),
+                (easter, "Ülestõusmispühade 1. püha"),
+                (date(5, 1), "Kevadpüha"),
+                (from_easter(49), "Nelipühade 1. püha"),
+                (date(6, 23), "Võidupüha"),
+                (date(6, 24), "Jaanipäev"),
+                (date(8, 20), "Taasiseseisvumispäev"),
+                (date(12, 24), "Jõululaupäev"),
+                (date(12, 25), "Esimene jõulupüha"),
+                (date(12, 26), "Teine jõulupüha"),
+            ],
+            HolidayCalendar::Latvia => vec![
+                (date(1, 1), "Jaungada diena"),
+                (from_easter(-2), "Lielā Piektdiena"),
+                (easter, "Lieldienas"),
+                (from_easter(1), "Otrās Lieldienas"),
+                (date(5, 1), "Darba svētki"),
+                (date(5, 4), "Neatkarības atjaunošanas diena"),
+                (date(6, 23), "Līgo diena"),
+                (date(6, 24), "Jāņu diena"),
+                (date(11, 18), "Proklamēšanas diena"),
+                (date(12, 24), "Ziemassvētku vakars"),
+                (date(12, 25), "Ziemassvētki"),
+                (date(12, 26), "Otrie Ziemassvētki"),
+                (date(12, 31), "Vecgada vakars"),
+            ],
+            HolidayCalendar::Lithuania => vec![
+                (date(1, 1), "Naujieji metai"),
+                (date(2, 16), "Valstybės atkūrimo diena"),
+                (date(3, 11), "Nepriklausomybės atkūrimo diena"),
+                (easter, "Velykos"),
+                (from_easter(1), "Antroji Velykų diena"),
+                (date(5, 1), "Darbo diena"),
+                (date(6, 24), "Joninės"),
+                (date(7, 6), "Valstybės diena"),
+                (date(8, 15), "Žolinė"),
+                (date(11, 1), "Visų šventųjų diena"),
+                (date(11, 2), "Vėlinės"),
+                (date(12, 24), "Kūčios"),
+                (date(12, 25), "Kalėdos"),
+                (date(12, 26), "Antroji Kalėdų diena"),
+            ],
+        };
+        holidays.sort();
+        holidays
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays(date.year()).iter().any(|(d, _)| *d == date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{easter, HolidayCalendar};
+
+    #[test]
+    fn easter_sunday() {
+        assert_eq!(easter(2022), NaiveDate::from_ymd(2022, 4, 17));
+        assert_eq!(easter(2023), NaiveDate::from_ymd(2023, 4, 9));
+        assert_eq!(easter(2024), NaiveDate::from_ymd(2024, 3, 31));
+        assert_eq!(easter(2025), NaiveDate::from_ymd(2025, 4, 20));
+    }
+
+    #[test]
+    fn swedish_holidays() {
+        let sweden = HolidayCalendar::Sweden;
+        assert!(sweden.is_holiday(NaiveDate::from_ymd(2023, 6, 23)));
+        assert!(sweden.is_holiday(NaiveDate::from_ymd(2023, 11, 4)));
+        assert!(sweden.is_holiday(NaiveDate::from_ymd(2023, 5, 18)));
+        assert!(sweden.is_holiday(NaiveDate::from_ymd(2022, 12, 24)));
+        assert!(!sweden.is_holiday(NaiveDate::from_ymd(2023, 6, 22)));
+        assert_eq!(sweden.holidays(2023).len(), 16);
+    }
+}
@@ -0,0 +1,268 @@
+//! Nord Pool's yearly historical price files, e.g.
+//! `elspot-prices_2022_hourly_sek.xls`.
+//!
+//! The files have a row per delivery hour in CET with a date and an hours
+//! column followed by a column per zone, priced per MWh with Nordic decimal
+//! commas. The `.xls` files are HTML tables, the CSV files are separated by
+//! semicolons.
+
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::CET;
+use rusty_money::iso::Currency;
+
+use crate::backend::{is_placeholder, parse_price, resolve_local_time};
+use crate::{BiddingZone, Diagnostic, Error, Resolution, SpotPrice, ZonePrices};
+
+/// Parses a historical price file in `currency` into the spot prices per kWh
+/// of each zone and the hours that could not be parsed, like the legacy
+/// marketdata page response.
+///
+/// Both the HTML `.xls` and the CSV files are accepted, encoded in UTF-8 or
+/// Windows-1252. Columns of unknown zones are ignored.
+pub fn parse_export(contents: &[u8], currency: &'static Currency) -> Result<ZonePrices, Error> {
+    let text = match std::str::from_utf8(contents) {
+        Ok(text) => text.to_string(),
+        // Windows-1252 matches Latin-1 for the letters in zone names.
+        Err(_) => contents.iter().map(|&b| char::from(b)).collect(),
+    };
+    let rows = if text.trim_start().starts_with('<') {
+        html_rows(&text)
+    } else {
+        csv_rows(&text)?
+    };
+    parse_rows(&rows, currency)
+}
+
+fn csv_rows(text: &str) -> Result<Vec<Vec<String>>, Error> {
+    let delimiter = if text.contains(';') { b';' } else { b',' };
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|record| {
+            record
+                .map(|r| r.iter().map(|cell| cell.trim().to_string()).collect())
+                .map_err(|e| Error::Document(e.to_string()))
+        })
+        .collect()
+}
+
+/// The cell texts of each row of the HTML tables in `html`.
+fn html_rows(html: &str) -> Vec<Vec<String>> {
+    // Lowercasing ASCII keeps byte offsets, so positions apply to `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut rows = vec![];
+    let mut offset = 0;
+    while let Some(start) = lower[offset..].find("<tr").map(|i| offset + i) {
+        let end = lower[start..]
+            .find("</tr")
+            .map_or(html.len(), |i| start + i);
+        rows.push(html_cells(&html[start..end], &lower[start..end]));
+        offset = end;
+    }
+    rows
+}
+
+fn html_cells(html: &str, lower: &str) -> Vec<String> {
+    let mut cells = vec![];
+    let mut offset = 0;
+    loop {
+        let start = [lower[offset..].find("<td"), lower[offset..].find("<th")]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(start) = start.map(|i| offset + i) else {
+            break;
+        };
+        let content = lower[start..]
+            .find('>')
+            .map_or(html.len(), |i| start + i + 1);
+        let end = lower[content..]
+            .find("</t")
+            .map_or(html.len(), |i| content + i);
+        cells.push(html_text(&html[content..end]));
+        offset = end;
+    }
+    cells
+}
+
+/// Strips tags and decodes the entities used in the files.
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&oslash;", "ø")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// The zone of a column header, including Trondheim, the name of NO3 in
+/// older files.
+fn zone(header: &str) -> Option<BiddingZone> {
+    if header.eq_ignore_ascii_case("Tr.heim") {
+        return Some(BiddingZone::NO3);
+    }
+    BiddingZone::from_str(header).ok()
+}
+
+/// The local delivery start of a row with a `31-12-2022` date and a
+/// `23 - 24` hours cell.
+fn start_time(row: &[String], hours: usize) -> Option<NaiveDateTime> {
+    let date = row.first()?;
+    let date = ["%d-%m-%Y", "%d.%m.%Y", "%Y-%m-%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())?;
+    let hour: String = row
+        .get(hours)?
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    date.and_hms_opt(hour.parse().ok()?, 0, 0)
+}
+
+fn parse_rows(rows: &[Vec<String>], currency: &'static Currency) -> Result<ZonePrices, Error> {
+    let is_hours = |cell: &String| cell.eq_ignore_ascii_case("hours");
+    let header = rows
+        .iter()
+        .position(|row| row.iter().any(is_hours))
+        .ok_or_else(|| Error::Document("no header row with an hours column".to_string()))?;
+    let hours = rows[header].iter().position(is_hours).unwrap_or_default();
+    let mut zones: Vec<(usize, BiddingZone)> = vec![];
+    for (column, name) in rows[header].iter().enumerate().skip(hours + 1) {
+        match zone(name) {
+            // Later duplicates, like Molde next to Tr.heim, are ignored.
+            Some(zone) if !zones.iter().any(|&(_, z)| z == zone) => zones.push((column, zone)),
+            _ => {}
+        }
+    }
+    if zones.is_empty() {
+        return Err(Error::Document("no zone columns".to_string()));
+    }
+
+    let mut prices: ZonePrices = zones
+        .iter()
+        .map(|&(_, zone)| (zone, (vec![], vec![])))
+        .collect();
+    let mut previous = None;
+    for row in &rows[header + 1..] {
+        // Title, average and empty rows have no delivery hour.
+        let Some(start_time) = start_time(row, hours) else {
+            continue;
+        };
+        // The hour repeated when daylight saving time ends is listed twice,
+        // in delivery order.
+        let resolved = resolve_local_time(start_time, CET, previous).ok();
+        previous = resolved.or(previous);
+        for &(column, zone) in &zones {
+            let value = row.get(column).map_or("", String::as_str);
+            let result = match resolved {
+                // The hour skipped when daylight saving time starts is listed
+                // without prices.
+                None if is_placeholder(value) => continue,
+                None => Err(Error::NonexistentLocalTime(start_time)),
+                Some(_) if is_placeholder(value) => Err(Error::MissingPrice(start_time)),
+                Some(instant) => parse_price(value, currency)
+                    .map(|price| SpotPrice {
+                        start_time: instant.with_timezone(&zone.timezone()),
+                        duration: Resolution::Hour.duration(),
+                        price: price / 1000,
+                    })
+                    .ok_or_else(|| Error::UnparsablePrice {
+                        start_time,
+                        value: value.to_string(),
+                    }),
+            };
+            let (zone_prices, diagnostics) = prices.get_mut(&zone).expect("zone column");
+            match result {
+                Ok(price) => zone_prices.push(price),
+                Err(error) => diagnostics.push(Diagnostic { start_time, error }),
+            }
+        }
+    }
+    Ok(prices)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono_tz::Europe::{Helsinki, Stockholm};
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::SEK;
+
+    use super::parse_export;
+    use crate::{BiddingZone, Error};
+
+    const HTML: &str = r#"<html><body><table>
+<thead>
+<tr><td colspan="4">Elspot Prices in SEK/MWh</td></tr>
+<tr><td>Data was last updated 01-11-2022</td></tr>
+<tr><td></td><td>Hours</td><td>SE3</td><td>FI</td><td>Tr.heim</td><td>Molde</td><td>XYZ</td></tr>
+</thead>
+<tr><td>30-10-2022</td><td>01&nbsp;-&nbsp;02</td><td>1&nbsp;012,50</td><td>900,00</td><td>10,00</td><td>20,00</td><td>1,00</td></tr>
+<tr><td>30-10-2022</td><td>02&nbsp;-&nbsp;03</td><td>1&nbsp;000,00</td><td>890,00</td><td>10,00</td><td>20,00</td><td>1,00</td></tr>
+<tr><td>30-10-2022</td><td>02&nbsp;-&nbsp;03</td><td>980,25</td><td></td><td>10,00</td><td>20,00</td><td>1,00</td></tr>
+<tr><td>30-10-2022</td><td>03&nbsp;-&nbsp;04</td><td>n/a</td><td>870,00</td><td>10,00</td><td>20,00</td><td>1,00</td></tr>
+</table></body></html>"#;
+
+    #[test]
+    fn html_daylight_saving_ends() {
+        let prices = parse_export(HTML.as_bytes(), SEK).unwrap();
+        assert_eq!(prices.len(), 3);
+
+        let (se3, diagnostics) = &prices[&BiddingZone::SE3];
+        assert_eq!(se3.len(), 3);
+        assert_eq!(se3[0].price.amount(), &dec!(1.0125));
+        assert_eq!(se3[1].start_time.to_rfc3339(), "2022-10-30T02:00:00+02:00");
+        assert_eq!(se3[2].start_time.to_rfc3339(), "2022-10-30T02:00:00+01:00");
+        assert!(matches!(
+            diagnostics[0].error,
+            Error::UnparsablePrice { .. }
+        ));
+
+        // Finnish prices are listed in CET too.
+        let (fi, diagnostics) = &prices[&BiddingZone::FI];
+        assert_eq!(
+            fi[0].start_time,
+            Helsinki.ymd(2022, 10, 30).and_hms(2, 0, 0)
+        );
+        assert!(matches!(diagnostics[0].error, Error::MissingPrice(_)));
+        assert_eq!(prices[&BiddingZone::NO3].0[0].price.amount(), &dec!(0.01));
+    }
+
+    #[test]
+    fn csv_daylight_saving_starts() {
+        // Tromsø encoded in Windows-1252.
+        let csv: &[u8] = b"\
+# Elspot prices in SEK/MWh;;;\n\
+;Hours;SYS;SE3;Troms\xf8\n\
+27-03-2022;01 - 02;\"1 500,00\";1 600,00;1 700,00\n\
+27-03-2022;02 - 03;;;\n\
+27-03-2022;03 - 04;1 400,00;1 450,00;1 460,00\n";
+        let prices = parse_export(csv, SEK).unwrap();
+        let (se3, diagnostics) = &prices[&BiddingZone::SE3];
+        assert!(diagnostics.is_empty());
+        assert_eq!(se3.len(), 2);
+        assert_eq!(se3[0].price.amount(), &dec!(1.6));
+        assert_eq!(
+            se3[1].start_time,
+            Stockholm.ymd(2022, 3, 27).and_hms(3, 0, 0)
+        );
+        assert_eq!(prices[&BiddingZone::NO4].0.len(), 2);
+
+        let result = parse_export(b"no;header\n1;2\n", SEK);
+        assert!(matches!(result, Err(Error::Document(_))));
+    }
+}
@@ -1,115 +1,517 @@
-use chrono::{Date, DateTime, Datelike, NaiveDateTime, Timelike, Weekday};
-use chrono_tz::{Europe::Stockholm, Tz};
-
-use rust_decimal_macros::dec;
-use rusty_money::iso::SEK;
-use serde::{Deserialize, Deserializer};
-
-type Money = rusty_money::Money<'static, rusty_money::iso::Currency>;
-
-const NORDPOOL_URL_HOUR: &str = "https://www.nordpoolgroup.com/api/marketdata/page/10";
-
-fn deserialize_partial_iso8601<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    NaiveDateTime::parse_from_str(&String::deserialize(deserializer)?, "%Y-%m-%dT%H:%M:%S")
-        .map_err(serde::de::Error::custom)
-}
-
-#[derive(Deserialize, Debug)]
-struct Response {
-    data: Data,
-}
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
-struct Data {
-    rows: Vec<Row>,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
-struct Row {
-    #[serde(deserialize_with = "deserialize_partial_iso8601")]
-    start_time: NaiveDateTime,
-    columns: Vec<Column>,
-    is_extra_row: bool,
-}
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
-struct Column {
-    name: String,
-    value: String,
-}
-
-pub async fn get_prices(area: &str, end_date: Date<Tz>) -> Result<Vec<TotalPrice>, reqwest::Error> {
-    let url = format!(
-        "{NORDPOOL_URL_HOUR}?currency=SEK&endDate={}",
-        end_date.format("%d-%m-%Y")
-    );
-    let response = reqwest::get(url).await?.json::<Response>().await?;
-
-    Ok(response
-        .data
-        .rows
-        .iter()
-        .filter(|r| !r.is_extra_row)
-        .flat_map(|r| {
-            r.columns
-                .iter()
-                .find(|c| c.name == area)
-                .and_then(|c| Money::from_str(&c.value, rusty_money::iso::SEK).ok())
-                .and_then(|price: Money| {
-                    r.start_time
-                        .and_local_timezone(Stockholm)
-                        .single()
-                        .map(|local_start_time| TotalPrice::compute(local_start_time, price / 1000))
-                })
+use std::collections::HashMap;
+
+use chrono::{Date, DateTime, Duration, NaiveDateTime};
+use chrono_tz::Tz;
+use futures::{stream, StreamExt, TryStreamExt};
+
+use rust_decimal::Decimal;
+use rusty_money::iso::{self, Currency};
+
+mod backend;
+mod cache;
+#[cfg(feature = "entsoe")]
+mod entsoe;
+mod error;
+mod holidays;
+#[cfg(feature = "import")]
+mod import;
+#[cfg(test)]
+mod mock;
+mod retry;
+mod series;
+mod source;
+mod spot;
+#[cfg(feature = "store")]
+mod store;
+mod tariff;
+mod zone;
+
+pub use backend::{Backend, DataPortal, Legacy, NordPoolClient, NordPoolClientBuilder};
+pub use cache::{CacheStats, CachedSource};
+#[cfg(feature = "entsoe")]
+pub use entsoe::{EntsoeClient, EntsoeClientBuilder};
+pub use error::Error;
+pub use holidays::{easter, HolidayCalendar};
+#[cfg(feature = "import")]
+pub use import::parse_export;
+pub use retry::RetryPolicy;
+pub use series::{Aggregation, Period, PriceSeries};
+pub use source::{MemorySource, PriceSource, ZonePrices};
+pub use spot::{resample, Resolution, SpotPrice};
+#[cfg(feature = "store")]
+pub use store::PriceStore;
+#[cfg(feature = "tariff-files")]
+pub use tariff::TariffError;
+pub use tariff::{
+    Component, ComponentKind, FixedCharge, MonthlyBill, Peak, PeakRule, PowerTariff, Rate, Season,
+    Tariff, VatRate,
+};
+pub use zone::{BiddingZone, ParseBiddingZoneError};
+
+type Money = rusty_money::Money<'static, Currency>;
+
+/// Currencies Nord Pool publishes spot prices in.
+pub const SUPPORTED_CURRENCIES: [&Currency; 4] = [iso::EUR, iso::DKK, iso::NOK, iso::SEK];
+
+/// A row of the response that was left out of the prices.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// Local delivery start of the row as published.
+    pub start_time: NaiveDateTime,
+    pub error: Error,
+}
+
+/// Prices fetched in lenient mode, with the rows that were left out.
+#[derive(Debug)]
+pub struct LenientPrices {
+    pub prices: Vec<TotalPrice>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Fetches the spot prices for `zone` in `currency` for the day ending at
+/// `end_date` from the Nord Pool Data Portal and computes the total price
+/// under `tariff` for each interval of `resolution`. Use
+/// [`BiddingZone::currency`] for the zone's local currency.
+///
+/// Published prices are averaged into longer intervals or split into equal
+/// parts for shorter ones, see [`resample`].
+///
+/// Fails if any row of the response cannot be turned into a price, see
+/// [`get_prices_lenient`] to skip such rows instead.
+pub async fn get_prices(
+    zone: BiddingZone,
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<Vec<TotalPrice>, Error> {
+    get_prices_from(
+        &NordPoolClient::new(),
+        zone,
+        currency,
+        end_date,
+        resolution,
+        tariff,
+    )
+    .await
+}
+
+/// Like [`get_prices`], but rows that cannot be turned into a price are
+/// skipped and reported as diagnostics. An interval of `resolution` that
+/// contains a skipped row is left out, see [`resample`].
+pub async fn get_prices_lenient(
+    zone: BiddingZone,
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<LenientPrices, Error> {
+    get_prices_lenient_from(
+        &NordPoolClient::new(),
+        zone,
+        currency,
+        end_date,
+        resolution,
+        tariff,
+    )
+    .await
+}
+
+/// Like [`get_prices`], fetching the spot prices from `source`, e.g. a
+/// [`NordPoolClient`] for the [`Legacy`] marketdata page endpoint.
+pub async fn get_prices_from(
+    source: &dyn PriceSource,
+    zone: BiddingZone,
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<Vec<TotalPrice>, Error> {
+    let LenientPrices {
+        prices,
+        diagnostics,
+    } = get_prices_lenient_from(source, zone, currency, end_date, resolution, tariff).await?;
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(diagnostic.error),
+        None => Ok(prices),
+    }
+}
+
+/// Like [`get_prices_lenient`], fetching the spot prices from `source`.
+pub async fn get_prices_lenient_from(
+    source: &dyn PriceSource,
+    zone: BiddingZone,
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<LenientPrices, Error> {
+    check_currency(currency, tariff)?;
+    let (spot_prices, diagnostics) = source
+        .spot_prices(zone, currency, end_date.naive_local())
+        .await?;
+    Ok(LenientPrices {
+        prices: total_prices(&spot_prices, resolution, tariff),
+        diagnostics,
+    })
+}
+
+/// Like [`get_prices`], for each of `zones` from a single request.
+///
+/// Fails with [`Error::UnknownArea`] if a zone is not published by the
+/// source; use [`get_prices_all`] for every zone that is.
+pub async fn get_prices_many(
+    zones: &[BiddingZone],
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+    get_prices_many_from(
+        &NordPoolClient::new(),
+        zones,
+        currency,
+        end_date,
+        resolution,
+        tariff,
+    )
+    .await
+}
+
+/// Like [`get_prices_many`], fetching the spot prices from `source`.
+pub async fn get_prices_many_from(
+    source: &dyn PriceSource,
+    zones: &[BiddingZone],
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+    check_currency(currency, tariff)?;
+    let prices = source
+        .spot_prices_many(zones, currency, end_date.naive_local())
+        .await?;
+    zone_totals(prices, resolution, tariff)
+}
+
+/// Like [`get_prices_many`], for every zone the source publishes for the
+/// day. Zones missing from the response, like Poland on the legacy
+/// marketdata page, are left out.
+pub async fn get_prices_all(
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+    get_prices_all_from(
+        &NordPoolClient::new(),
+        currency,
+        end_date,
+        resolution,
+        tariff,
+    )
+    .await
+}
+
+/// Like [`get_prices_all`], fetching the spot prices from `source`.
+pub async fn get_prices_all_from(
+    source: &dyn PriceSource,
+    currency: &'static Currency,
+    end_date: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+    check_currency(currency, tariff)?;
+    let prices = source
+        .spot_prices_all(currency, end_date.naive_local())
+        .await?;
+    zone_totals(prices, resolution, tariff)
+}
+
+/// The total prices of each zone, failing on the first diagnostic.
+fn zone_totals(
+    prices: ZonePrices,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<HashMap<BiddingZone, Vec<TotalPrice>>, Error> {
+    prices
+        .into_iter()
+        .map(|(zone, (spot_prices, diagnostics))| {
+            if let Some(diagnostic) = diagnostics.into_iter().next() {
+                return Err(diagnostic.error);
+            }
+            Ok((zone, total_prices(&spot_prices, resolution, tariff)))
         })
-        .collect())
+        .collect()
+}
+
+/// Number of days [`get_prices_range_from`] fetches at the same time.
+pub(crate) const RANGE_CONCURRENCY: usize = 4;
+
+/// Like [`get_prices`], for every day from `from` to `to` inclusive.
+pub async fn get_prices_range(
+    zone: BiddingZone,
+    currency: &'static Currency,
+    from: Date<Tz>,
+    to: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<Vec<TotalPrice>, Error> {
+    get_prices_range_from(
+        &NordPoolClient::new(),
+        zone,
+        currency,
+        from,
+        to,
+        resolution,
+        tariff,
+    )
+    .await
+}
+
+/// Like [`get_prices_range`], fetching the spot prices from `source`.
+///
+/// Days are fetched a few at a time. Intervals published on more than one
+/// day are only kept once, and the result fails with [`Error::Gap`] if an
+/// interval does not end where the next one starts.
+pub async fn get_prices_range_from(
+    source: &dyn PriceSource,
+    zone: BiddingZone,
+    currency: &'static Currency,
+    from: Date<Tz>,
+    to: Date<Tz>,
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Result<Vec<TotalPrice>, Error> {
+    check_currency(currency, tariff)?;
+    let mut days = vec![];
+    let mut date = from.naive_local();
+    while date <= to.naive_local() {
+        days.push(date);
+        date = date.succ();
+    }
+    let days: Vec<_> = stream::iter(days)
+        .map(|date| source.spot_prices(zone, currency, date))
+        .buffered(RANGE_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut spot_prices = vec![];
+    for (prices, diagnostics) in days {
+        if let Some(diagnostic) = diagnostics.into_iter().next() {
+            return Err(diagnostic.error);
+        }
+        spot_prices.extend(prices);
+    }
+    spot_prices.sort_by_key(|p| p.start_time);
+    spot_prices.dedup_by_key(|p| p.start_time);
+    if let Some(pair) = spot_prices
+        .windows(2)
+        .find(|pair| pair[0].end_time() != pair[1].start_time)
+    {
+        return Err(Error::Gap {
+            from: pair[0].end_time(),
+            to: pair[1].start_time,
+        });
+    }
+    Ok(total_prices(&spot_prices, resolution, tariff))
+}
+
+fn check_currency(currency: &'static Currency, tariff: &Tariff) -> Result<(), Error> {
+    if tariff.currency != currency {
+        return Err(Error::CurrencyMismatch {
+            tariff: tariff.currency.iso_alpha_code,
+            prices: currency.iso_alpha_code,
+        });
+    }
+    Ok(())
+}
+
+fn total_prices(
+    spot_prices: &[SpotPrice],
+    resolution: Resolution,
+    tariff: &Tariff,
+) -> Vec<TotalPrice> {
+    resample(spot_prices, resolution)
+        .into_iter()
+        .map(|p| TotalPrice::compute(p.start_time, p.duration, p.price, tariff))
+        .collect()
+}
+
+/// What a line item of a [`TotalPrice`] is charged for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineItemKind {
+    /// The spot price.
+    Spot,
+    /// A tariff component added to the spot price, e.g. a supplier markup.
+    Energy,
+    Fee,
+    Tax,
+    Vat,
 }
 
+impl From<ComponentKind> for LineItemKind {
+    fn from(kind: ComponentKind) -> Self {
+        match kind {
+            ComponentKind::Energy => LineItemKind::Energy,
+            ComponentKind::Fee => LineItemKind::Fee,
+            ComponentKind::Tax => LineItemKind::Tax,
+        }
+    }
+}
+
+/// A named part of a [`TotalPrice`], per kWh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineItem {
+    pub name: String,
+    pub kind: LineItemKind,
+    pub amount: Money,
+}
+
+/// The price per kWh of one interval, broken down into line items.
+#[derive(Debug, Clone, PartialEq)]
 pub struct TotalPrice {
     start_time: DateTime<Tz>,
-    energy: Money,
-    vat: Money,
-    fee: Money,
-    tax: Money,
+    duration: Duration,
+    items: Vec<LineItem>,
 }
 
 impl TotalPrice {
-    pub fn compute(start_time: DateTime<Tz>, energy: Money) -> Self {
+    /// Computes the total price for the interval of `duration` starting at
+    /// `start_time` from the spot price per kWh in `energy`. Time-of-use
+    /// rates are matched on the start of the interval.
+    ///
+    /// Panics if `energy` is not in the tariff currency.
+    pub fn compute(
+        start_time: DateTime<Tz>,
+        duration: Duration,
+        energy: Money,
+        tariff: &Tariff,
+    ) -> Self {
+        assert_eq!(
+            energy.currency(),
+            tariff.currency,
+            "tariff currency mismatch"
+        );
+        let components: Vec<_> = tariff.components_at(start_time).collect();
+        // The spot price is always VAT-liable.
+        let vat_base = *energy.amount()
+            + components
+                .iter()
+                .filter(|(component, _)| component.vat_liable)
+                .map(|(_, amount)| amount)
+                .sum::<Decimal>();
+        let mut items = vec![LineItem {
+            name: "Spot price".to_string(),
+            kind: LineItemKind::Spot,
+            amount: energy,
+        }];
+        items.extend(components.into_iter().map(|(component, amount)| LineItem {
+            name: component.name.clone(),
+            kind: component.kind.into(),
+            amount: Money::from_decimal(amount, tariff.currency),
+        }));
+        items.push(LineItem {
+            name: "VAT".to_string(),
+            kind: LineItemKind::Vat,
+            amount: Money::from_decimal(vat_base * tariff.vat_rate(start_time), tariff.currency),
+        });
         Self {
             start_time,
-            energy: energy.clone(),
-            vat: energy * dec!(0.25),
-            fee: match (start_time.weekday(), start_time.time().hour()) {
-                (_, 0..=5) | (_, 22..) | (Weekday::Sat, _) | (Weekday::Sun, _) => {
-                    Money::from_minor(12, SEK)
-                }
-                (_, _) => Money::from_minor(70, SEK),
-            },
-            tax: Money::from_minor(45, SEK),
+            duration,
+            items,
         }
     }
+
+    /// All line items, the spot price first and VAT last.
+    pub fn items(&self) -> &[LineItem] {
+        &self.items
+    }
+
+    /// Sum of the line items of `kind`.
+    pub fn total(&self, kind: LineItemKind) -> Money {
+        let currency = self.items[0].amount.currency();
+        Money::from_decimal(
+            self.items
+                .iter()
+                .filter(|i| i.kind == kind)
+                .map(|i| *i.amount.amount())
+                .sum(),
+            currency,
+        )
+    }
+
+    pub fn spot(&self) -> Money {
+        self.total(LineItemKind::Spot)
+    }
+
+    /// The spot price plus energy components such as supplier markups.
+    pub fn energy(&self) -> Money {
+        self.spot() + self.total(LineItemKind::Energy)
+    }
+
+    pub fn fee(&self) -> Money {
+        self.total(LineItemKind::Fee)
+    }
+
+    pub fn tax(&self) -> Money {
+        self.total(LineItemKind::Tax)
+    }
+
+    pub fn vat(&self) -> Money {
+        self.total(LineItemKind::Vat)
+    }
+
     pub fn sum(&self) -> Money {
-        self.energy.to_owned() + self.fee.to_owned() + self.tax.to_owned() + self.vat.to_owned()
+        let currency = self.items[0].amount.currency();
+        Money::from_decimal(
+            self.items.iter().map(|i| *i.amount.amount()).sum(),
+            currency,
+        )
     }
+
     pub fn start_time(&self) -> DateTime<Tz> {
         self.start_time
     }
+
+    pub fn end_time(&self) -> DateTime<Tz> {
+        self.start_time + self.duration
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::TimeZone;
+    use chrono::{Duration, TimeZone};
     use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::{SEK, USD};
+
+    use crate::mock::{MockServer, PRICES};
+    use crate::{
+        BiddingZone, Error, LineItemKind, Money, NordPoolClient, Resolution, Tariff, TotalPrice,
+    };
 
     #[tokio::test]
     async fn get_prices() {
-        let prices = super::get_prices("SE3", Stockholm.ymd(2022, 11, 9))
-            .await
-            .expect("Error fetching prices.");
+        let server = MockServer::start(vec![("200 OK", PRICES)]).await;
+        let client = NordPoolClient::builder()
+            .base_url(&server.url)
+            .build()
+            .unwrap();
+        let prices = super::get_prices_from(
+            &client,
+            BiddingZone::SE3,
+            SEK,
+            Stockholm.ymd(2024, 10, 27),
+            Resolution::Hour,
+            &Tariff::default(),
+        )
+        .await
+        .expect("Error fetching prices.");
 
         println!(
             "{:26}{:8}{:8}{:8}{:8}{:8}",
@@ -118,14 +520,89 @@ mod tests {
         for price in prices.iter() {
             println!(
                 "{:24}{:>8}{:>8}{:>8}{:>8}{:>8}",
-                price.start_time.to_string(),
-                price.energy.to_string(),
-                price.vat.to_string(),
-                price.fee.to_string(),
-                price.tax.to_string(),
+                price.start_time().to_string(),
+                price.energy().to_string(),
+                price.vat().to_string(),
+                price.fee().to_string(),
+                price.tax().to_string(),
                 price.sum().to_string()
             );
         }
-        assert_eq!(prices.len(), 24);
+        // The day daylight saving time ends has 25 hours.
+        assert_eq!(prices.len(), 25);
+    }
+
+    #[tokio::test]
+    async fn unsupported_currency() {
+        let result = super::get_prices(
+            BiddingZone::SE3,
+            USD,
+            Stockholm.ymd(2022, 11, 9),
+            Resolution::Hour,
+            &Tariff {
+                currency: USD,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::UnsupportedCurrency("USD"))));
+    }
+
+    #[test]
+    fn line_items() {
+        let start_time = Stockholm.ymd(2022, 11, 9).and_hms(12, 0, 0);
+        let price = TotalPrice::compute(
+            start_time,
+            Duration::hours(1),
+            Money::from_minor(100, SEK),
+            &Tariff::default(),
+        );
+        let items: Vec<_> = price
+            .items()
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind, *i.amount.amount()))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                ("Spot price", LineItemKind::Spot, dec!(1.00)),
+                ("Grid fee", LineItemKind::Fee, dec!(0.70)),
+                ("Energy tax", LineItemKind::Tax, dec!(0.45)),
+                ("VAT", LineItemKind::Vat, dec!(0.5375)),
+            ]
+        );
+        assert_eq!(price.sum().amount(), &dec!(2.6875));
+        assert_eq!(price.end_time(), start_time + Duration::hours(1));
+    }
+
+    #[test]
+    fn vat_liable_components() {
+        let mut tariff = Tariff::default();
+        tariff.components[1].vat_liable = false;
+        let start_time = Stockholm.ymd(2022, 11, 9).and_hms(12, 0, 0);
+        let price = TotalPrice::compute(
+            start_time,
+            Duration::hours(1),
+            Money::from_minor(100, SEK),
+            &tariff,
+        );
+        assert_eq!(price.vat().amount(), &dec!(0.425));
+    }
+
+    #[test]
+    fn quarter_hour_fees() {
+        // The time-of-use fee changes at 06:00 within the hour's quarters.
+        let start_time = Stockholm.ymd(2022, 11, 9).and_hms(5, 45, 0);
+        let price = |start_time| {
+            TotalPrice::compute(
+                start_time,
+                Duration::minutes(15),
+                Money::from_minor(100, SEK),
+                &Tariff::default(),
+            )
+        };
+        assert_eq!(price(start_time).fee().amount(), &dec!(0.12));
+        let next = price(start_time).end_time();
+        assert_eq!(price(next).fee().amount(), &dec!(0.70));
     }
 }
@@ -0,0 +1,85 @@
+//! Helpers for testing sources against a local server and fixed prices.
+
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Date, Duration};
+use chrono_tz::Tz;
+use rusty_money::iso::SEK;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{Money, SpotPrice};
+
+/// An HTTP server on localhost answering each connection with the next
+/// of `responses` and recording the request heads.
+pub struct MockServer {
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// `responses` are raw status lines with headers, e.g.
+    /// `"503 Service Unavailable\r\nRetry-After: 1"`, and bodies. A
+    /// `Content-Length` in the head replaces that of the body, e.g. to cut
+    /// the body short.
+    pub async fn start(responses: Vec<(&'static str, &'static str)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            for (head, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut buffer = [0; 4096];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buffer).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&request).into_owned());
+                let length = if head.to_ascii_lowercase().contains("content-length") {
+                    String::new()
+                } else {
+                    format!("\r\nContent-Length: {}", body.len())
+                };
+                let response =
+                    format!("HTTP/1.1 {head}{length}\r\nConnection: close\r\n\r\n{body}");
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        Self { url, requests }
+    }
+}
+
+pub const PRICES: &str = include_str!("../fixtures/dataportal-2024-10-27-SE3-NO1-SEK.json");
+
+/// The hourly SEK prices of `date` at the positions in `range`, 23 or 25
+/// hours around daylight saving changes. The price of the hour at position
+/// `i` is `100 + i` öre.
+pub fn hours(date: Date<Tz>, range: impl RangeBounds<usize>) -> Vec<SpotPrice> {
+    let (mut start_time, end) = (date.and_hms(0, 0, 0), date.succ().and_hms(0, 0, 0));
+    let mut prices = vec![];
+    let mut position = 0;
+    while start_time < end {
+        if range.contains(&position) {
+            prices.push(SpotPrice {
+                start_time,
+                duration: Duration::hours(1),
+                price: Money::from_minor(100 + position as i64, SEK),
+            });
+        }
+        start_time += Duration::hours(1);
+        position += 1;
+    }
+    prices
+}
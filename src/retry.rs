@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+
+use crate::Error;
+
+/// When and how often a failed request is retried.
+///
+/// Requests are retried after transport errors, like connection resets and
+/// timeouts, and after responses with one of the retryable statuses. The
+/// delay before attempt `n + 1` is `initial_backoff * 2^(n - 1)`, capped at
+/// `max_backoff` and shortened by a random share of up to `jitter`, unless
+/// the response asks for a delay with `Retry-After`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts including the first request, at least 1.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    /// Longest delay between attempts. A `Retry-After` longer than this
+    /// ends the retries.
+    pub max_backoff: Duration,
+    /// Share of each backoff, between 0 and 1, that is randomly left out so
+    /// that clients do not retry in lockstep.
+    pub jitter: f64,
+    pub retryable_statuses: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts starting at half a second, retrying 429 and the
+    /// gateway and availability errors 500, 502, 503 and 504.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.5,
+            retryable_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends every request once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before the attempt after `attempt`, without jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retrying a request that failed in `attempt`, or `None`
+    /// if it should not be retried.
+    pub(crate) fn delay(
+        &self,
+        attempt: u32,
+        result: &Result<Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        match result {
+            Ok(response) if self.retryable_statuses.contains(&response.status()) => {
+                match retry_after(response) {
+                    Some(delay) if delay > self.max_backoff => None,
+                    Some(delay) => Some(delay),
+                    None => Some(self.jittered(self.backoff(attempt))),
+                }
+            }
+            Ok(_) => None,
+            Err(e) if e.is_builder() => None,
+            Err(_) => Some(self.jittered(self.backoff(attempt))),
+        }
+    }
+
+    /// Sends a GET request to `url` with `http` and reads the body of a
+    /// successful response, retrying both as configured. Fails with
+    /// [`Error::Status`] for other responses, wrapped in
+    /// [`Error::RetriesExhausted`] if the request was retried.
+    pub(crate) async fn get(
+        &self,
+        http: &reqwest::Client,
+        url: &str,
+    ) -> Result<(StatusCode, Vec<u8>), Error> {
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            let result = match http.get(url).send().await {
+                Ok(response) if response.status().is_success() => {
+                    let status = response.status();
+                    // The connection may also fail while the body is read.
+                    match response.bytes().await {
+                        Ok(body) => return Ok((status, body.to_vec())),
+                        Err(e) => Err(e),
+                    }
+                }
+                result => result,
+            };
+            match self.delay(attempts, &result) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => break result,
+            }
+        };
+        let error = match result {
+            Ok(response) => Error::Status(response.status()),
+            Err(e) => e.into(),
+        };
+        if attempts > 1 {
+            Err(Error::RetriesExhausted {
+                attempts,
+                last: Box::new(error),
+            })
+        } else {
+            Err(error)
+        }
+    }
+
+    fn jittered(&self, delay: Duration) -> Duration {
+        delay.mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * fastrand::f64())
+    }
+}
+
+/// The `Retry-After` header as a delay, given in seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Stockholm;
+    use reqwest::StatusCode;
+
+    use super::RetryPolicy;
+    use crate::mock::{MockServer, PRICES};
+    use crate::{BiddingZone, Error, NordPoolClient, Resolution, Tariff};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_secs(2),
+            ..Default::default()
+        }
+    }
+
+    async fn get_prices(server: &MockServer, retry: RetryPolicy) -> Result<usize, Error> {
+        let client = NordPoolClient::builder()
+            .base_url(&server.url)
+            .retry(retry)
+            .build()
+            .unwrap();
+        let tariff = Tariff::default();
+        client
+            .get_prices(
+                BiddingZone::SE3,
+                tariff.currency,
+                Stockholm.ymd(2024, 10, 27),
+                Resolution::Hour,
+                &tariff,
+            )
+            .await
+            .map(|prices| prices.len())
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            ..Default::default()
+        };
+        let backoff: Vec<_> = (1..=4).map(|attempt| policy.backoff(attempt)).collect();
+        assert_eq!(backoff, [1, 2, 4, 5].map(Duration::from_secs),);
+        let delay = policy.jittered(Duration::from_secs(4));
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn retries_unavailable() {
+        let server = MockServer::start(vec![
+            ("503 Service Unavailable", ""),
+            ("502 Bad Gateway", ""),
+            ("200 OK", PRICES),
+        ])
+        .await;
+        assert_eq!(get_prices(&server, policy(3)).await.unwrap(), 25);
+        assert_eq!(server.requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn reports_attempts() {
+        let server = MockServer::start(vec![
+            ("503 Service Unavailable", ""),
+            ("503 Service Unavailable", ""),
+        ])
+        .await;
+        let result = get_prices(&server, policy(2)).await;
+        let error = result.unwrap_err();
+        assert_eq!(error.to_string(), "gave up after 2 attempts");
+        assert!(std::error::Error::source(&error).is_some());
+        assert!(matches!(
+            error,
+            Error::RetriesExhausted { attempts: 2, last }
+                if matches!(*last, Error::Status(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+
+        // Client errors are not retried.
+        let server = MockServer::start(vec![("404 Not Found", "")]).await;
+        let result = get_prices(&server, policy(3)).await;
+        assert!(matches!(result, Err(Error::Status(StatusCode::NOT_FOUND))));
+    }
+
+    #[tokio::test]
+    async fn respects_retry_after() {
+        let server = MockServer::start(vec![
+            ("429 Too Many Requests\r\nRetry-After: 1", ""),
+            ("200 OK", PRICES),
+        ])
+        .await;
+        let start = Instant::now();
+        assert_eq!(get_prices(&server, policy(2)).await.unwrap(), 25);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+
+        // Longer than the maximum backoff.
+        let server =
+            MockServer::start(vec![("503 Service Unavailable\r\nRetry-After: 60", "")]).await;
+        let result = get_prices(&server, policy(3)).await;
+        assert!(matches!(
+            result,
+            Err(Error::Status(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_truncated_body() {
+        let server = MockServer::start(vec![
+            ("200 OK\r\nContent-Length: 100000", "{\"deliveryDateCET\""),
+            ("200 OK", PRICES),
+        ])
+        .await;
+        assert_eq!(get_prices(&server, policy(2)).await.unwrap(), 25);
+        assert_eq!(server.requests.lock().unwrap().len(), 2);
+
+        let server = MockServer::start(vec![
+            ("200 OK\r\nContent-Length: 100000", "{\"deliveryDateCET\""),
+            ("200 OK\r\nContent-Length: 100000", "{\"deliveryDateCET\""),
+        ])
+        .await;
+        let result = get_prices(&server, policy(2)).await;
+        assert!(matches!(
+            result,
+            Err(Error::RetriesExhausted { attempts: 2, last }) if matches!(*last, Error::Transport(_))
+        ));
+    }
+}
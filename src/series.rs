@@ -0,0 +1,313 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+
+use crate::{LineItem, Money, TotalPrice};
+
+/// A calendar period prices are aggregated over, in the local time of the
+/// prices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Period {
+    Hour,
+    Day,
+    /// An ISO week, starting on Monday.
+    Week,
+    Month,
+}
+
+/// The first instant of `date`, or of the first hour after midnight if
+/// midnight falls in a daylight saving gap.
+pub(crate) fn start_of_day(tz: Tz, date: NaiveDate) -> DateTime<Tz> {
+    let midnight = date.and_hms(0, 0, 0);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| tz.from_utc_datetime(&midnight))
+}
+
+impl Period {
+    /// Start and end of the period containing `t`. Days are 23 or 25 hours
+    /// long when daylight saving time starts or ends.
+    fn bounds(&self, t: DateTime<Tz>) -> (DateTime<Tz>, DateTime<Tz>) {
+        let tz = t.timezone();
+        let date = t.date_naive();
+        let (first, next) = match self {
+            Period::Hour => {
+                let start = tz.timestamp(t.timestamp().div_euclid(3600) * 3600, 0);
+                return (start, start + Duration::hours(1));
+            }
+            Period::Day => (date, date.succ()),
+            Period::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday().into());
+                (monday, monday + Duration::days(7))
+            }
+            Period::Month => {
+                let first = NaiveDate::from_ymd(date.year(), date.month(), 1);
+                let next = if date.month() == 12 {
+                    NaiveDate::from_ymd(date.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd(date.year(), date.month() + 1, 1)
+                };
+                (first, next)
+            }
+        };
+        (start_of_day(tz, first), start_of_day(tz, next))
+    }
+}
+
+/// How the prices of a period are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    /// Average with every interval counting the same.
+    Mean,
+    /// The interval with the lowest total price.
+    Min,
+    /// The interval with the highest total price.
+    Max,
+    /// Average weighted by interval duration.
+    TimeWeighted,
+    /// Average weighted by the volumes set with
+    /// [`PriceSeries::with_volumes`], i.e. the price actually paid per kWh.
+    VolumeWeighted,
+}
+
+/// Total prices sorted by start time, with an optional consumed volume per
+/// interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSeries {
+    prices: Vec<TotalPrice>,
+    /// kWh per interval of `prices`.
+    volumes: Vec<Decimal>,
+}
+
+impl PriceSeries {
+    pub fn new(mut prices: Vec<TotalPrice>) -> Self {
+        prices.sort_by_key(|p| p.start_time);
+        let volumes = vec![Decimal::ZERO; prices.len()];
+        Self { prices, volumes }
+    }
+
+    /// Sets the volume of each interval to the consumption starting in it.
+    /// `consumption` is a series of start times and kWh, like in
+    /// [`PowerTariff::compute`](crate::PowerTariff::compute).
+    pub fn with_volumes(mut self, consumption: &[(DateTime<Tz>, Decimal)]) -> Self {
+        self.volumes = vec![Decimal::ZERO; self.prices.len()];
+        for &(start_time, kwh) in consumption {
+            let i = self.prices.partition_point(|p| p.start_time <= start_time);
+            if i > 0 && start_time < self.prices[i - 1].end_time() {
+                self.volumes[i - 1] += kwh;
+            }
+        }
+        self
+    }
+
+    pub fn prices(&self) -> &[TotalPrice] {
+        &self.prices
+    }
+
+    pub fn into_prices(self) -> Vec<TotalPrice> {
+        self.prices
+    }
+
+    /// kWh per interval, zero unless set with [`PriceSeries::with_volumes`].
+    pub fn volumes(&self) -> &[Decimal] {
+        &self.volumes
+    }
+
+    /// The intervals for which `predicate` holds, e.g. peak hours.
+    pub fn filter(&self, predicate: impl Fn(&TotalPrice) -> bool) -> PriceSeries {
+        let (prices, volumes) = self
+            .prices
+            .iter()
+            .zip(&self.volumes)
+            .filter(|(price, _)| predicate(price))
+            .map(|(price, volume)| (price.clone(), *volume))
+            .unzip();
+        PriceSeries { prices, volumes }
+    }
+
+    /// Combines the intervals of each `period` into one price spanning the
+    /// period. Intervals are assigned to the period they start in, so
+    /// `period` should be at least as long as the intervals.
+    ///
+    /// Line items are aggregated by name and kind, so the result can be
+    /// broken down like any [`TotalPrice`]. [`Aggregation::Min`] and
+    /// [`Aggregation::Max`] keep the line items of the selected interval.
+    /// Periods without volume are left out of [`Aggregation::VolumeWeighted`]
+    /// results. The volume of an aggregated price is the sum of its
+    /// intervals.
+    pub fn aggregate(&self, period: Period, aggregation: Aggregation) -> PriceSeries {
+        let mut buckets: Vec<(DateTime<Tz>, DateTime<Tz>, Vec<usize>)> = Vec::new();
+        for (i, price) in self.prices.iter().enumerate() {
+            let (start, end) = period.bounds(price.start_time);
+            match buckets.last_mut() {
+                Some((last, _, indices)) if *last == start => indices.push(i),
+                _ => buckets.push((start, end, vec![i])),
+            }
+        }
+
+        let mut series = PriceSeries::new(Vec::new());
+        for (start_time, end_time, indices) in buckets {
+            let items = match aggregation {
+                Aggregation::Min => indices
+                    .iter()
+                    .min_by_key(|&&i| *self.prices[i].sum().amount())
+                    .map(|&i| self.prices[i].items.clone()),
+                Aggregation::Max => indices
+                    .iter()
+                    .max_by_key(|&&i| *self.prices[i].sum().amount())
+                    .map(|&i| self.prices[i].items.clone()),
+                Aggregation::Mean => self.weighted(&indices, |_| Decimal::ONE),
+                Aggregation::TimeWeighted => self.weighted(&indices, |i| {
+                    Decimal::from(self.prices[i].duration.num_seconds())
+                }),
+                Aggregation::VolumeWeighted => self.weighted(&indices, |i| self.volumes[i]),
+            };
+            if let Some(items) = items {
+                series.prices.push(TotalPrice {
+                    start_time,
+                    duration: end_time - start_time,
+                    items,
+                });
+                series
+                    .volumes
+                    .push(indices.iter().map(|&i| self.volumes[i]).sum());
+            }
+        }
+        series
+    }
+
+    /// Weighted average of the line items of the intervals at `indices`, or
+    /// `None` if the weights sum to zero.
+    fn weighted(
+        &self,
+        indices: &[usize],
+        weight: impl Fn(usize) -> Decimal,
+    ) -> Option<Vec<LineItem>> {
+        let total: Decimal = indices.iter().map(|&i| weight(i)).sum();
+        if total.is_zero() {
+            return None;
+        }
+        let mut items: Vec<LineItem> = Vec::new();
+        for &i in indices {
+            let weight = weight(i);
+            for item in &self.prices[i].items {
+                let amount = item.amount.clone() * weight;
+                match items
+                    .iter_mut()
+                    .find(|a| a.name == item.name && a.kind == item.kind)
+                {
+                    Some(aggregated) => aggregated.amount += amount,
+                    None => items.push(LineItem {
+                        name: item.name.clone(),
+                        kind: item.kind,
+                        amount,
+                    }),
+                }
+            }
+        }
+        // Divide once so that averages of equal prices come out exact.
+        for item in &mut items {
+            item.amount = Money::from_decimal(item.amount.amount() / total, item.amount.currency());
+        }
+        Some(items)
+    }
+}
+
+impl From<Vec<TotalPrice>> for PriceSeries {
+    fn from(prices: Vec<TotalPrice>) -> Self {
+        PriceSeries::new(prices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Timelike};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::SEK;
+
+    use super::{Aggregation, Period, PriceSeries};
+    use crate::{Money, Tariff, TotalPrice};
+
+    /// Hourly prices from `start` with a spot price of `spot(i)` öre.
+    fn hours(count: i64, spot: impl Fn(i64) -> i64) -> PriceSeries {
+        let start = Stockholm.ymd(2022, 10, 29).and_hms(0, 0, 0);
+        let tariff = Tariff {
+            components: Vec::new(),
+            vat: Vec::new(),
+            ..Default::default()
+        };
+        (0..count)
+            .map(|i| {
+                TotalPrice::compute(
+                    start + Duration::hours(i),
+                    Duration::hours(1),
+                    Money::from_minor(spot(i), SEK),
+                    &tariff,
+                )
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn daily_across_dst() {
+        // 24 hours on the 29th, 25 on the 30th when daylight saving ends.
+        let series = hours(49, |i| if i < 24 { 100 } else { 200 });
+        let days = series.aggregate(Period::Day, Aggregation::Mean);
+        assert_eq!(days.prices().len(), 2);
+        assert_eq!(days.prices()[0].duration(), Duration::hours(24));
+        assert_eq!(days.prices()[0].spot().amount(), &dec!(1));
+        assert_eq!(days.prices()[1].duration(), Duration::hours(25));
+        assert_eq!(days.prices()[1].spot().amount(), &dec!(2));
+        assert_eq!(
+            days.prices()[1].end_time(),
+            Stockholm.ymd(2022, 10, 31).and_hms(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn min_max_and_volume_weighted() {
+        let series = hours(24, |i| 100 + i);
+        let max = series.aggregate(Period::Day, Aggregation::Max);
+        assert_eq!(max.prices()[0].spot().amount(), &dec!(1.23));
+        let min = series.aggregate(Period::Day, Aggregation::Min);
+        assert_eq!(min.prices()[0].spot().amount(), &dec!(1.00));
+
+        assert!(series
+            .aggregate(Period::Day, Aggregation::VolumeWeighted)
+            .prices()
+            .is_empty());
+        let consumption: Vec<_> = series
+            .prices()
+            .iter()
+            .filter(|p| p.start_time().hour() < 2)
+            .map(|p| (p.start_time(), dec!(1.5)))
+            .collect();
+        let paid = series
+            .with_volumes(&consumption)
+            .aggregate(Period::Day, Aggregation::VolumeWeighted);
+        assert_eq!(paid.prices()[0].spot().amount(), &dec!(1.005));
+        assert_eq!(paid.volumes(), &[dec!(3)]);
+    }
+
+    #[test]
+    fn peak_hours_by_week() {
+        let series = hours(24 * 14, |i| i % 24);
+        let peak = series
+            .filter(|p| (7..19).contains(&p.start_time().hour()))
+            .aggregate(Period::Week, Aggregation::TimeWeighted);
+        // The 29th is a Saturday, so the first week has two days.
+        assert_eq!(peak.prices().len(), 3);
+        assert_eq!(
+            peak.prices()[1].start_time(),
+            Stockholm.ymd(2022, 10, 31).and_hms(0, 0, 0)
+        );
+        // Local hours 7 to 18 are indices 8 to 19 after the 25-hour day.
+        assert_eq!(peak.prices()[1].spot().amount(), &dec!(0.135));
+    }
+}
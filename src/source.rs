@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rusty_money::iso::Currency;
+
+use crate::{BiddingZone, Diagnostic, Error, SpotPrice};
+
+/// The spot prices and diagnostics of each zone fetched at once.
+pub type ZonePrices = HashMap<BiddingZone, (Vec<SpotPrice>, Vec<Diagnostic>)>;
+
+/// A provider of day-ahead spot prices, e.g. Nord Pool through a
+/// [`NordPoolClient`](crate::NordPoolClient).
+///
+/// Sources only return spot prices, so the same tariff computations apply to
+/// the prices of any source, see [`get_prices_from`](crate::get_prices_from).
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// The spot prices per kWh of `zone` in `currency` for the delivery day
+    /// `date`, and the intervals that could not be turned into a price.
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error>;
+
+    /// The spot prices of each of `zones` for the delivery day `date`. The
+    /// default requests the zones one by one; sources that publish several
+    /// zones in one response fetch them at once.
+    async fn spot_prices_many(
+        &self,
+        zones: &[BiddingZone],
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let mut prices = HashMap::with_capacity(zones.len());
+        for &zone in zones {
+            prices.insert(zone, self.spot_prices(zone, currency, date).await?);
+        }
+        Ok(prices)
+    }
+
+    /// The spot prices of every zone the source publishes for the delivery
+    /// day `date`. The default requests each of [`BiddingZone::ALL`] and
+    /// leaves out the zones that fail with [`Error::UnknownArea`].
+    async fn spot_prices_all(
+        &self,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let mut prices = HashMap::new();
+        for zone in BiddingZone::ALL {
+            match self.spot_prices(zone, currency, date).await {
+                Ok(zone_prices) => {
+                    prices.insert(zone, zone_prices);
+                }
+                Err(Error::UnknownArea(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(prices)
+    }
+}
+
+/// A source serving prices inserted up front, for tests and recorded data.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    prices: HashMap<(BiddingZone, NaiveDate), Vec<SpotPrice>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prices of `zone` for the delivery day `date`.
+    pub fn insert(&mut self, zone: BiddingZone, date: NaiveDate, prices: Vec<SpotPrice>) {
+        self.prices.insert((zone, date), prices);
+    }
+}
+
+#[async_trait]
+impl PriceSource for MemorySource {
+    /// Fails with [`Error::NotPublished`] for days without prices and with
+    /// [`Error::UnsupportedCurrency`] if the prices are in another currency.
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        let prices = self
+            .prices
+            .get(&(zone, date))
+            .ok_or(Error::NotPublished(date))?;
+        if prices.iter().any(|p| p.price.currency() != currency) {
+            return Err(Error::UnsupportedCurrency(currency.iso_alpha_code));
+        }
+        Ok((prices.clone(), vec![]))
+    }
+
+    /// The zones with prices for `date`, failing with
+    /// [`Error::NotPublished`] if there are none.
+    async fn spot_prices_all(
+        &self,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<ZonePrices, Error> {
+        let mut prices = HashMap::new();
+        for &(zone, day) in self.prices.keys() {
+            if day == date {
+                prices.insert(zone, self.spot_prices(zone, currency, date).await?);
+            }
+        }
+        if prices.is_empty() {
+            return Err(Error::NotPublished(date));
+        }
+        Ok(prices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration as StdDuration;
+
+    use async_trait::async_trait;
+    use chrono::{Date, NaiveDate, TimeZone};
+    use chrono_tz::{Europe::Stockholm, Tz};
+    use rusty_money::iso::{Currency, EUR, SEK};
+
+    use super::{MemorySource, PriceSource};
+    use crate::mock::hours;
+    use crate::{BiddingZone, Diagnostic, Error, Resolution, SpotPrice, Tariff, TotalPrice};
+
+    #[tokio::test]
+    async fn tariff_on_memory_source() {
+        let date = Stockholm.ymd(2022, 11, 9);
+        let spot = hours(date, 0..24);
+        let mut source = MemorySource::new();
+        source.insert(BiddingZone::SE3, date.naive_local(), spot.clone());
+
+        let tariff = Tariff::default();
+        let prices = crate::get_prices_from(
+            &source,
+            BiddingZone::SE3,
+            SEK,
+            date,
+            Resolution::Hour,
+            &tariff,
+        )
+        .await
+        .unwrap();
+        let expected: Vec<TotalPrice> = spot
+            .into_iter()
+            .map(|p| TotalPrice::compute(p.start_time, p.duration, p.price, &tariff))
+            .collect();
+        assert_eq!(prices, expected);
+
+        let quarters = crate::get_prices_from(
+            &source,
+            BiddingZone::SE3,
+            SEK,
+            date,
+            Resolution::QuarterHour,
+            &tariff,
+        )
+        .await
+        .unwrap();
+        assert_eq!(quarters.len(), 96);
+
+        let result = crate::get_prices_from(
+            &source,
+            BiddingZone::SE4,
+            SEK,
+            date,
+            Resolution::Hour,
+            &tariff,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::NotPublished(_))));
+        let all = crate::get_prices_all_from(&source, SEK, date, Resolution::Hour, &tariff)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[&BiddingZone::SE3], expected);
+        let result = crate::get_prices_from(
+            &source,
+            BiddingZone::SE3,
+            EUR,
+            date,
+            Resolution::Hour,
+            &Tariff {
+                currency: EUR,
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::UnsupportedCurrency("EUR"))));
+    }
+
+    async fn range(
+        source: &MemorySource,
+        from: Date<Tz>,
+        to: Date<Tz>,
+    ) -> Result<Vec<TotalPrice>, Error> {
+        let tariff = Tariff::default();
+        crate::get_prices_range_from(
+            source,
+            BiddingZone::SE3,
+            SEK,
+            from,
+            to,
+            Resolution::Hour,
+            &tariff,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn prices_range() {
+        let days: Vec<_> = (1..=3).map(|d| Stockholm.ymd(2022, 11, d)).collect();
+        let mut source = MemorySource::new();
+        source.insert(
+            BiddingZone::SE3,
+            days[0].naive_local(),
+            hours(days[0], 0..24),
+        );
+        // Overlaps the previous day by the first hours of the next.
+        let mut second = hours(days[0], 20..24);
+        second.extend(hours(days[1], 0..24));
+        source.insert(BiddingZone::SE3, days[1].naive_local(), second);
+        source.insert(
+            BiddingZone::SE3,
+            days[2].naive_local(),
+            hours(days[2], 0..24),
+        );
+
+        let prices = range(&source, days[0], days[2]).await.unwrap();
+        assert_eq!(prices.len(), 72);
+        assert_eq!(prices[0].start_time(), days[0].and_hms(0, 0, 0));
+        for pair in prices.windows(2) {
+            assert_eq!(pair[0].end_time(), pair[1].start_time());
+        }
+
+        source.insert(
+            BiddingZone::SE3,
+            days[1].naive_local(),
+            hours(days[1], 1..24),
+        );
+        let result = range(&source, days[0], days[2]).await;
+        assert!(matches!(
+            result,
+            Err(Error::Gap { from, to })
+                if from == days[1].and_hms(0, 0, 0) && to == days[1].and_hms(1, 0, 0)
+        ));
+        let result = range(&source, days[0], Stockholm.ymd(2022, 11, 4)).await;
+        assert!(matches!(result, Err(Error::NotPublished(_))));
+    }
+
+    /// Counts how many days are requested at the same time.
+    #[derive(Default)]
+    struct CountingSource {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for CountingSource {
+        async fn spot_prices(
+            &self,
+            _zone: BiddingZone,
+            _currency: &'static Currency,
+            date: NaiveDate,
+        ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(StdDuration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let date = Stockholm.from_local_date(&date).unwrap();
+            Ok((hours(date, 0..24), vec![]))
+        }
+    }
+
+    #[tokio::test]
+    async fn bounded_concurrency() {
+        let source = CountingSource::default();
+        let tariff = Tariff::default();
+        let prices = crate::get_prices_range_from(
+            &source,
+            BiddingZone::SE3,
+            SEK,
+            Stockholm.ymd(2022, 11, 1),
+            Stockholm.ymd(2022, 11, 30),
+            Resolution::Hour,
+            &tariff,
+        )
+        .await
+        .unwrap();
+        assert_eq!(prices.len(), 30 * 24);
+        let max_in_flight = source.max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 1 && max_in_flight <= 4);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, TimeZone};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+
+use crate::Money;
+
+/// Length of a market time unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Resolution {
+    QuarterHour,
+    HalfHour,
+    Hour,
+}
+
+impl Resolution {
+    pub fn minutes(&self) -> i64 {
+        match self {
+            Resolution::QuarterHour => 15,
+            Resolution::HalfHour => 30,
+            Resolution::Hour => 60,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::minutes(self.minutes())
+    }
+}
+
+/// The spot price per kWh of one delivery interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotPrice {
+    pub start_time: DateTime<Tz>,
+    pub duration: Duration,
+    pub price: Money,
+}
+
+impl SpotPrice {
+    pub fn end_time(&self) -> DateTime<Tz> {
+        self.start_time + self.duration
+    }
+}
+
+/// Converts `prices` to `resolution`. Intervals longer than `resolution` are
+/// split into intervals with the same price, shorter ones are averaged over
+/// each interval of `resolution`. An interval of `resolution` that `prices`
+/// only partly cover, e.g. an hour with a quarter missing, is left out
+/// rather than averaged over the part that is covered.
+///
+/// Intervals are aligned on UTC, which matches local clock time in all zones
+/// with whole-hour offsets.
+pub fn resample(prices: &[SpotPrice], resolution: Resolution) -> Vec<SpotPrice> {
+    let step = resolution.duration();
+    let seconds = step.num_seconds();
+    // Bucket start timestamp -> (first price, sum of price * seconds, seconds).
+    let mut buckets: BTreeMap<i64, (&SpotPrice, Decimal, i64)> = BTreeMap::new();
+    for price in prices {
+        let mut start = price.start_time;
+        while start < price.end_time() {
+            let bucket = start.timestamp().div_euclid(seconds) * seconds;
+            let end = price.end_time().min(start + step);
+            let length = (end - start).num_seconds();
+            let entry = buckets.entry(bucket).or_insert((price, Decimal::ZERO, 0));
+            entry.1 += price.price.amount() * Decimal::from(length);
+            entry.2 += length;
+            start = end;
+        }
+    }
+    buckets
+        .into_iter()
+        .filter(|(_, (_, _, length))| *length == seconds)
+        .map(|(bucket, (first, weighted, length))| SpotPrice {
+            start_time: first.start_time.timezone().timestamp(bucket, 0),
+            duration: step,
+            price: Money::from_decimal(weighted / Decimal::from(length), first.price.currency()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::SEK;
+
+    use super::{resample, Resolution, SpotPrice};
+    use crate::Money;
+
+    fn quarters(prices: &[i64]) -> Vec<SpotPrice> {
+        let start = Stockholm.ymd(2025, 10, 26).and_hms(0, 0, 0);
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| SpotPrice {
+                start_time: start + Duration::minutes(15 * i as i64),
+                duration: Duration::minutes(15),
+                price: Money::from_minor(p, SEK),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aggregate_to_hours() {
+        let hours = resample(
+            &quarters(&[10, 20, 30, 40, 50, 50, 50, 50]),
+            Resolution::Hour,
+        );
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[0].price.amount(), &dec!(0.25));
+        assert_eq!(hours[1].price.amount(), &dec!(0.50));
+        assert_eq!(
+            hours[1].start_time,
+            Stockholm.ymd(2025, 10, 26).and_hms(1, 0, 0)
+        );
+        assert_eq!(hours[1].duration, Duration::hours(1));
+    }
+
+    #[test]
+    fn split_hours() {
+        let hours = resample(&quarters(&[10, 20, 30, 40]), Resolution::Hour);
+        let quarters = resample(&hours, Resolution::QuarterHour);
+        assert_eq!(quarters.len(), 4);
+        assert!(quarters.iter().all(|q| q.price.amount() == &dec!(0.25)));
+        assert_eq!(quarters[3].end_time(), hours[0].end_time());
+    }
+
+    #[test]
+    fn skips_partly_covered_intervals() {
+        let mut prices = quarters(&[10, 20, 30, 40, 50, 50, 50, 50]);
+        prices.remove(2);
+        let hours = resample(&prices, Resolution::Hour);
+        assert_eq!(hours.len(), 1);
+        assert_eq!(
+            hours[0].start_time,
+            Stockholm.ymd(2025, 10, 26).and_hms(1, 0, 0)
+        );
+        assert_eq!(hours[0].price.amount(), &dec!(0.50));
+    }
+}
@@ -0,0 +1,721 @@
+//! A SQLite store of historical spot prices.
+//!
+//! Spot prices are stored as published, per interval and currency. Totals
+//! are computed from them with the tariff passed to each query, so changing
+//! a tariff never requires fetching again; computed prices are only written
+//! when saved explicitly for analysis in SQL.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::{Date, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures::{stream, StreamExt};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Transaction};
+use rust_decimal::Decimal;
+use rusty_money::iso::{self, Currency};
+
+use crate::cache::is_final;
+use crate::{
+    BiddingZone, Diagnostic, Error, Money, PriceSource, Resolution, SpotPrice, Tariff, TotalPrice,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS zones (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    timezone TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS currencies (
+    code TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+-- Zones and currencies kept up to date by sync().
+CREATE TABLE IF NOT EXISTS tracked (
+    zone_id INTEGER NOT NULL REFERENCES zones (id),
+    currency TEXT NOT NULL REFERENCES currencies (code),
+    since TEXT NOT NULL,
+    PRIMARY KEY (zone_id, currency)
+);
+-- Start as UTC Unix timestamp and duration in seconds.
+CREATE TABLE IF NOT EXISTS intervals (
+    id INTEGER PRIMARY KEY,
+    zone_id INTEGER NOT NULL REFERENCES zones (id),
+    start_time INTEGER NOT NULL,
+    duration INTEGER NOT NULL,
+    UNIQUE (zone_id, start_time, duration)
+);
+-- Spot prices per kWh as published for the delivery day.
+CREATE TABLE IF NOT EXISTS spot_prices (
+    interval_id INTEGER NOT NULL REFERENCES intervals (id),
+    currency TEXT NOT NULL REFERENCES currencies (code),
+    date TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    PRIMARY KEY (interval_id, currency)
+);
+-- Delivery days whose spot prices are final.
+CREATE TABLE IF NOT EXISTS days (
+    zone_id INTEGER NOT NULL REFERENCES zones (id),
+    currency TEXT NOT NULL REFERENCES currencies (code),
+    date TEXT NOT NULL,
+    PRIMARY KEY (zone_id, currency, date)
+);
+-- Local start times of the intervals of final days published without a
+-- price. backfill() fetches such days again until the gaps are filled.
+CREATE TABLE IF NOT EXISTS missing_prices (
+    zone_id INTEGER NOT NULL REFERENCES zones (id),
+    currency TEXT NOT NULL REFERENCES currencies (code),
+    date TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    PRIMARY KEY (zone_id, currency, date, start_time)
+);
+-- Totals saved with save_computed(), per named tariff.
+CREATE TABLE IF NOT EXISTS computed_prices (
+    interval_id INTEGER NOT NULL REFERENCES intervals (id),
+    currency TEXT NOT NULL REFERENCES currencies (code),
+    tariff TEXT NOT NULL,
+    energy TEXT NOT NULL,
+    fee TEXT NOT NULL,
+    tax TEXT NOT NULL,
+    vat TEXT NOT NULL,
+    total TEXT NOT NULL,
+    PRIMARY KEY (interval_id, currency, tariff)
+);
+";
+
+/// Historical spot prices in a SQLite database, fetched from a source.
+///
+/// The store is itself a [`PriceSource`] serving the stored days, so the
+/// same queries apply as to any other source.
+pub struct PriceStore<S> {
+    connection: Mutex<Connection>,
+    source: S,
+}
+
+impl<S: PriceSource> PriceStore<S> {
+    /// Opens or creates the database at `path`, fetching missing prices
+    /// from `source`.
+    pub fn open(path: impl AsRef<Path>, source: S) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?, source)
+    }
+
+    pub fn open_in_memory(source: S) -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?, source)
+    }
+
+    fn with_connection(connection: Connection, source: S) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            source,
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().expect("store connection poisoned")
+    }
+
+    /// Keeps the prices of `zone` in `currency` from the delivery day
+    /// `since` on, see [`PriceStore::sync`].
+    pub fn track(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        since: NaiveDate,
+    ) -> Result<(), Error> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let zone_id = zone_id(&transaction, zone, currency)?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO tracked (zone_id, currency, since) VALUES (?1, ?2, ?3)",
+            params![zone_id, currency.iso_alpha_code, since.to_string()],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn tracked(&self) -> Result<Vec<(BiddingZone, &'static Currency, NaiveDate)>, Error> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT zones.name, tracked.currency, tracked.since
+             FROM tracked JOIN zones ON zones.id = tracked.zone_id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            let zone = row.get::<_, String>(0)?;
+            let zone = BiddingZone::from_str(&zone).map_err(|e| conversion_error(0, e))?;
+            let currency = row.get::<_, String>(1)?;
+            let currency = iso::find(&currency).ok_or(rusqlite::Error::InvalidColumnType(
+                1,
+                "currency".to_string(),
+                Type::Text,
+            ))?;
+            Ok((zone, currency, date(row, 2)?))
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn stored_days(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<HashSet<NaiveDate>, Error> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT days.date FROM days JOIN zones ON zones.id = days.zone_id
+             WHERE zones.name = ?1 AND days.currency = ?2",
+        )?;
+        let rows = statement.query_map(params![zone.name(), currency.iso_alpha_code], |row| {
+            date(row, 0)
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The local start times of the intervals of `date` stored without a
+    /// price.
+    fn missing_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<Vec<NaiveDateTime>, Error> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT missing_prices.start_time
+             FROM missing_prices JOIN zones ON zones.id = missing_prices.zone_id
+             WHERE zones.name = ?1 AND missing_prices.currency = ?2 AND missing_prices.date = ?3
+             ORDER BY missing_prices.start_time",
+        )?;
+        let rows = statement.query_map(
+            params![zone.name(), currency.iso_alpha_code, date.to_string()],
+            |row| {
+                let value = row.get::<_, String>(0)?;
+                NaiveDateTime::from_str(&value).map_err(|e| conversion_error(0, e))
+            },
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The stored days with intervals published without a price.
+    fn days_with_gaps(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+    ) -> Result<HashSet<NaiveDate>, Error> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT DISTINCT missing_prices.date
+             FROM missing_prices JOIN zones ON zones.id = missing_prices.zone_id
+             WHERE zones.name = ?1 AND missing_prices.currency = ?2",
+        )?;
+        let rows = statement.query_map(params![zone.name(), currency.iso_alpha_code], |row| {
+            date(row, 0)
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Fetches the days from `from` to `to` inclusive that are not stored
+    /// yet or were published with gaps, for every tracked zone, and returns
+    /// how many were fetched. Days that are not published yet are skipped.
+    pub async fn backfill(&self, from: NaiveDate, to: NaiveDate) -> Result<usize, Error> {
+        let mut missing = vec![];
+        for (zone, currency, _) in self.tracked()? {
+            let mut stored = self.stored_days(zone, currency)?;
+            for date in self.days_with_gaps(zone, currency)? {
+                stored.remove(&date);
+            }
+            missing.extend(missing_days(&stored, zone, currency, from, to));
+        }
+        self.fetch(missing).await
+    }
+
+    /// Fetches the days since each zone is tracked, up to tomorrow, that are
+    /// not stored yet. Days with gaps are only fetched again by
+    /// [`PriceStore::backfill`].
+    pub async fn sync(&self) -> Result<usize, Error> {
+        let mut missing = vec![];
+        for (zone, currency, since) in self.tracked()? {
+            let today = Utc::now().with_timezone(&zone.timezone()).date_naive();
+            let stored = self.stored_days(zone, currency)?;
+            missing.extend(missing_days(&stored, zone, currency, since, today.succ()));
+        }
+        self.fetch(missing).await
+    }
+
+    async fn fetch(
+        &self,
+        days: Vec<(BiddingZone, &'static Currency, NaiveDate)>,
+    ) -> Result<usize, Error> {
+        let mut results = stream::iter(days)
+            .map(|(zone, currency, date)| async move {
+                let result = self.source.spot_prices(zone, currency, date).await;
+                (zone, currency, date, result)
+            })
+            .buffer_unordered(crate::RANGE_CONCURRENCY);
+        let mut fetched = 0;
+        while let Some((zone, currency, date, result)) = results.next().await {
+            let (prices, diagnostics) = match result {
+                Err(Error::NotPublished(_)) => continue,
+                result => result?,
+            };
+            self.insert(zone, currency, date, &prices, &diagnostics)?;
+            fetched += 1;
+        }
+        Ok(fetched)
+    }
+
+    /// Stores the spot prices of a delivery day in place of those stored
+    /// before, marking the day as stored once its prices are final and
+    /// replacing the intervals it was stored without a price for.
+    fn insert(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+        prices: &[SpotPrice],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), Error> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let zone_id = zone_id(&transaction, zone, currency)?;
+        // The day may have been stored before with fewer intervals, other
+        // amounts or in another resolution.
+        transaction.execute(
+            "DELETE FROM spot_prices
+             WHERE currency = ?2 AND date = ?3
+             AND interval_id IN (SELECT id FROM intervals WHERE zone_id = ?1)",
+            params![zone_id, currency.iso_alpha_code, date.to_string()],
+        )?;
+        for price in prices {
+            let interval_id = interval_id(&transaction, zone_id, price.start_time, price.duration)?;
+            transaction.execute(
+                "INSERT INTO spot_prices (interval_id, currency, date, amount)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (interval_id, currency)
+                 DO UPDATE SET date = excluded.date, amount = excluded.amount",
+                params![
+                    interval_id,
+                    currency.iso_alpha_code,
+                    date.to_string(),
+                    price.price.amount().to_string()
+                ],
+            )?;
+        }
+        if is_final(zone, date, prices, diagnostics) {
+            let key = params![zone_id, currency.iso_alpha_code, date.to_string()];
+            transaction.execute(
+                "INSERT OR IGNORE INTO days (zone_id, currency, date) VALUES (?1, ?2, ?3)",
+                key,
+            )?;
+            transaction.execute(
+                "DELETE FROM missing_prices WHERE zone_id = ?1 AND currency = ?2 AND date = ?3",
+                key,
+            )?;
+            // Final days only have diagnostics for missing prices.
+            for diagnostic in diagnostics {
+                transaction.execute(
+                    "INSERT OR IGNORE INTO missing_prices (zone_id, currency, date, start_time)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        zone_id,
+                        currency.iso_alpha_code,
+                        date.to_string(),
+                        diagnostic
+                            .start_time
+                            .format("%Y-%m-%dT%H:%M:%S")
+                            .to_string()
+                    ],
+                )?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The prices of the delivery days from `from` to `to` inclusive,
+    /// computed with `tariff`, see
+    /// [`get_prices_range`](crate::get_prices_range).
+    pub async fn prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        from: Date<Tz>,
+        to: Date<Tz>,
+        resolution: Resolution,
+        tariff: &Tariff,
+    ) -> Result<Vec<TotalPrice>, Error> {
+        crate::get_prices_range_from(self, zone, currency, from, to, resolution, tariff).await
+    }
+
+    /// Saves the computed `prices` of `zone` under the name `tariff`,
+    /// replacing earlier totals of the same intervals.
+    pub fn save_computed(
+        &self,
+        tariff: &str,
+        zone: BiddingZone,
+        prices: &[TotalPrice],
+    ) -> Result<(), Error> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        for price in prices {
+            let currency = price.sum().currency();
+            let zone_id = zone_id(&transaction, zone, currency)?;
+            let interval_id =
+                interval_id(&transaction, zone_id, price.start_time(), price.duration())?;
+            transaction.execute(
+                "INSERT OR REPLACE INTO computed_prices
+                 (interval_id, currency, tariff, energy, fee, tax, vat, total)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    interval_id,
+                    currency.iso_alpha_code,
+                    tariff,
+                    price.energy().amount().to_string(),
+                    price.fee().amount().to_string(),
+                    price.tax().amount().to_string(),
+                    price.vat().amount().to_string(),
+                    price.sum().amount().to_string()
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// The days from `from` to `to` inclusive that are not in `stored`.
+fn missing_days(
+    stored: &HashSet<NaiveDate>,
+    zone: BiddingZone,
+    currency: &'static Currency,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<(BiddingZone, &'static Currency, NaiveDate)> {
+    let mut missing = vec![];
+    let mut date = from;
+    while date <= to {
+        if !stored.contains(&date) {
+            missing.push((zone, currency, date));
+        }
+        date = date.succ();
+    }
+    missing
+}
+
+/// The id of `zone`, inserting the zone and `currency` if needed.
+fn zone_id(
+    transaction: &Transaction,
+    zone: BiddingZone,
+    currency: &'static Currency,
+) -> Result<i64, Error> {
+    transaction.execute(
+        "INSERT OR IGNORE INTO currencies (code, name) VALUES (?1, ?2)",
+        params![currency.iso_alpha_code, currency.name],
+    )?;
+    transaction.execute(
+        "INSERT OR IGNORE INTO zones (name, timezone) VALUES (?1, ?2)",
+        params![zone.name(), zone.timezone().name()],
+    )?;
+    Ok(transaction.query_row(
+        "SELECT id FROM zones WHERE name = ?1",
+        params![zone.name()],
+        |row| row.get(0),
+    )?)
+}
+
+fn interval_id<T: TimeZone>(
+    transaction: &Transaction,
+    zone_id: i64,
+    start_time: chrono::DateTime<T>,
+    duration: Duration,
+) -> Result<i64, Error> {
+    let key = params![zone_id, start_time.timestamp(), duration.num_seconds()];
+    transaction.execute(
+        "INSERT OR IGNORE INTO intervals (zone_id, start_time, duration) VALUES (?1, ?2, ?3)",
+        key,
+    )?;
+    Ok(transaction.query_row(
+        "SELECT id FROM intervals WHERE zone_id = ?1 AND start_time = ?2 AND duration = ?3",
+        key,
+        |row| row.get(0),
+    )?)
+}
+
+/// Reads a `YYYY-MM-DD` date column.
+fn date(row: &rusqlite::Row, column: usize) -> rusqlite::Result<NaiveDate> {
+    let value = row.get::<_, String>(column)?;
+    NaiveDate::from_str(&value).map_err(|e| conversion_error(column, e))
+}
+
+fn conversion_error(
+    column: usize,
+    error: impl std::error::Error + Send + Sync + 'static,
+) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(error))
+}
+
+#[async_trait]
+impl<S: PriceSource> PriceSource for PriceStore<S> {
+    /// Serves stored days, with a diagnostic for each interval published
+    /// without a price, and fails with [`Error::NotCached`] for others.
+    async fn spot_prices(
+        &self,
+        zone: BiddingZone,
+        currency: &'static Currency,
+        date: NaiveDate,
+    ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+        if !self.stored_days(zone, currency)?.contains(&date) {
+            return Err(Error::NotCached(date));
+        }
+        let diagnostics = self
+            .missing_prices(zone, currency, date)?
+            .into_iter()
+            .map(|start_time| Diagnostic {
+                start_time,
+                error: Error::MissingPrice(start_time),
+            })
+            .collect();
+        let tz = zone.timezone();
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT intervals.start_time, intervals.duration, spot_prices.amount
+             FROM spot_prices
+             JOIN intervals ON intervals.id = spot_prices.interval_id
+             JOIN zones ON zones.id = intervals.zone_id
+             WHERE zones.name = ?1 AND spot_prices.currency = ?2 AND spot_prices.date = ?3
+             ORDER BY intervals.start_time",
+        )?;
+        let rows = statement.query_map(
+            params![zone.name(), currency.iso_alpha_code, date.to_string()],
+            |row| {
+                let amount = row.get::<_, String>(2)?;
+                let amount = Decimal::from_str(&amount).map_err(|e| conversion_error(2, e))?;
+                Ok(SpotPrice {
+                    start_time: tz.timestamp(row.get(0)?, 0),
+                    duration: Duration::seconds(row.get(1)?),
+                    price: Money::from_decimal(amount, currency),
+                })
+            },
+        )?;
+        Ok((rows.collect::<Result<_, _>>()?, diagnostics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+    use chrono::{Date, Duration, NaiveDate, TimeZone, Utc};
+    use chrono_tz::{Europe::Stockholm, Tz};
+    use rusty_money::iso::{Currency, SEK};
+
+    use super::PriceStore;
+    use crate::mock::hours;
+    use crate::{
+        BiddingZone, Diagnostic, Error, MemorySource, Money, PriceSource, Resolution, SpotPrice,
+        Tariff,
+    };
+
+    fn source(days: &[Date<Tz>]) -> MemorySource {
+        let mut source = MemorySource::new();
+        for &date in days {
+            source.insert(BiddingZone::SE3, date.naive_local(), hours(date, ..));
+        }
+        source
+    }
+
+    /// Publishes the last hour of a day without a price the first time.
+    #[derive(Default)]
+    struct Late {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for Late {
+        async fn spot_prices(
+            &self,
+            _zone: BiddingZone,
+            _currency: &'static Currency,
+            date: NaiveDate,
+        ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+            let local = Stockholm.from_local_date(&date).unwrap();
+            if self.calls.fetch_add(1, Ordering::Relaxed) > 0 {
+                return Ok((hours(local, ..), vec![]));
+            }
+            let start_time = date.and_hms(23, 0, 0);
+            let diagnostic = Diagnostic {
+                start_time,
+                error: Error::MissingPrice(start_time),
+            };
+            Ok((hours(local, ..23), vec![diagnostic]))
+        }
+    }
+
+    /// Publishes the first half of a day in hours the first time, then the
+    /// whole day in quarter hours at another price.
+    #[derive(Default)]
+    struct Revised {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for Revised {
+        async fn spot_prices(
+            &self,
+            _zone: BiddingZone,
+            _currency: &'static Currency,
+            date: NaiveDate,
+        ) -> Result<(Vec<SpotPrice>, Vec<Diagnostic>), Error> {
+            let local = Stockholm.from_local_date(&date).unwrap();
+            if self.calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                return Ok((hours(local, 0..12), vec![]));
+            }
+            let quarters = hours(local, ..)
+                .into_iter()
+                .flat_map(|hour| {
+                    (0..4).map(move |quarter| SpotPrice {
+                        start_time: hour.start_time + Duration::minutes(15 * quarter),
+                        duration: Duration::minutes(15),
+                        price: Money::from_minor(50, SEK),
+                    })
+                })
+                .collect();
+            Ok((quarters, vec![]))
+        }
+    }
+
+    #[tokio::test]
+    async fn backfill_fills_gaps() {
+        let days: Vec<_> = (1..=5).map(|d| Stockholm.ymd(2022, 11, d)).collect();
+        let store = PriceStore::open_in_memory(source(&days)).unwrap();
+        store
+            .track(BiddingZone::SE3, SEK, days[0].naive_local())
+            .unwrap();
+
+        let (first, last) = (days[0].naive_local(), days[4].naive_local());
+        assert_eq!(
+            store.backfill(days[1].naive_local(), last).await.unwrap(),
+            4
+        );
+        assert_eq!(store.backfill(first, last).await.unwrap(), 1);
+        assert_eq!(store.backfill(first, last).await.unwrap(), 0);
+
+        let tariff = Tariff::default();
+        let prices = store
+            .prices(
+                BiddingZone::SE3,
+                SEK,
+                days[0],
+                days[4],
+                Resolution::Hour,
+                &tariff,
+            )
+            .await
+            .unwrap();
+        let expected = crate::get_prices_range_from(
+            &source(&days),
+            BiddingZone::SE3,
+            SEK,
+            days[0],
+            days[4],
+            Resolution::Hour,
+            &tariff,
+        )
+        .await
+        .unwrap();
+        assert_eq!(prices, expected);
+
+        store
+            .save_computed("default", BiddingZone::SE3, &prices)
+            .unwrap();
+        let saved: i64 = store
+            .connection()
+            .query_row("SELECT COUNT(*) FROM computed_prices", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(saved, 5 * 24);
+
+        let result = store
+            .prices(
+                BiddingZone::SE3,
+                SEK,
+                days[0],
+                Stockholm.ymd(2022, 11, 6),
+                Resolution::Hour,
+                &tariff,
+            )
+            .await;
+        assert!(matches!(result, Err(Error::NotCached(_))));
+    }
+
+    #[tokio::test]
+    async fn sync_fetches_missing_days() {
+        let today = Utc::now().with_timezone(&Stockholm).date();
+        let days: Vec<_> = (0..3).map(|d| today - Duration::days(2 - d)).collect();
+        let store = PriceStore::open_in_memory(source(&days)).unwrap();
+        store
+            .track(BiddingZone::SE3, SEK, days[0].naive_local())
+            .unwrap();
+
+        store
+            .backfill(days[0].naive_local(), days[0].naive_local())
+            .await
+            .unwrap();
+        // Tomorrow is not published yet.
+        assert_eq!(store.sync().await.unwrap(), 2);
+        assert_eq!(store.sync().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn backfill_refetches_missing_prices() {
+        let date = Stockholm.ymd(2022, 11, 9);
+        let day = date.naive_local();
+        let store = PriceStore::open_in_memory(Late::default()).unwrap();
+        store.track(BiddingZone::SE3, SEK, day).unwrap();
+        let tariff = Tariff::default();
+
+        assert_eq!(store.backfill(day, day).await.unwrap(), 1);
+        let result = store
+            .prices(BiddingZone::SE3, SEK, date, date, Resolution::Hour, &tariff)
+            .await;
+        assert!(matches!(result, Err(Error::MissingPrice(t)) if t == day.and_hms(23, 0, 0)));
+
+        // The day is fetched again until the hour is published.
+        assert_eq!(store.backfill(day, day).await.unwrap(), 1);
+        assert_eq!(store.backfill(day, day).await.unwrap(), 0);
+        let prices = store
+            .prices(BiddingZone::SE3, SEK, date, date, Resolution::Hour, &tariff)
+            .await
+            .unwrap();
+        assert_eq!(prices.len(), 24);
+    }
+
+    #[tokio::test]
+    async fn refetch_replaces_partial_day() {
+        let date = Stockholm.ymd(2022, 11, 9);
+        let day = date.naive_local();
+        let store = PriceStore::open_in_memory(Revised::default()).unwrap();
+        store.track(BiddingZone::SE3, SEK, day).unwrap();
+
+        // Half a day is not final, so it is not served and fetched again.
+        assert_eq!(store.backfill(day, day).await.unwrap(), 1);
+        let result = store.spot_prices(BiddingZone::SE3, SEK, day).await;
+        assert!(matches!(result, Err(Error::NotCached(_))));
+        assert_eq!(store.backfill(day, day).await.unwrap(), 1);
+        assert_eq!(store.backfill(day, day).await.unwrap(), 0);
+
+        // The hourly prices stored first are replaced.
+        let (prices, _) = store.spot_prices(BiddingZone::SE3, SEK, day).await.unwrap();
+        assert_eq!(prices.len(), 96);
+        assert!(prices.iter().all(|p| p.price == Money::from_minor(50, SEK)));
+        let tariff = Tariff::default();
+        let totals = store
+            .prices(BiddingZone::SE3, SEK, date, date, Resolution::Hour, &tariff)
+            .await
+            .unwrap();
+        assert_eq!(totals.len(), 24);
+    }
+}
@@ -0,0 +1,369 @@
+use std::ops::Range;
+
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Weekday};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use rusty_money::iso::{self, Currency};
+use serde::Deserialize;
+
+use crate::{HolidayCalendar, TotalPrice};
+
+#[cfg(feature = "tariff-files")]
+mod config;
+mod power;
+
+#[cfg(feature = "tariff-files")]
+pub use config::TariffError;
+pub use power::{MonthlyBill, Peak, PeakRule, PowerTariff};
+
+/// What a tariff component is charged for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentKind {
+    /// Added to the spot price, e.g. a supplier markup.
+    Energy,
+    /// Network (grid) fee.
+    Fee,
+    /// Energy tax.
+    Tax,
+}
+
+/// A recurring part of the year given as `(month, day)` bounds, both
+/// inclusive. Wraps around new year when `from` is after `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Season {
+    pub from: (u32, u32),
+    pub to: (u32, u32),
+}
+
+impl Season {
+    /// The Nordic high-load season, November through March.
+    pub const WINTER: Season = Season {
+        from: (11, 1),
+        to: (3, 31),
+    };
+    /// April through October.
+    pub const SUMMER: Season = Season {
+        from: (4, 1),
+        to: (10, 31),
+    };
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let day = (date.month(), date.day());
+        if self.from <= self.to {
+            self.from <= day && day <= self.to
+        } else {
+            self.from <= day || day <= self.to
+        }
+    }
+}
+
+/// A per-kWh rate. An unset condition matches any time.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rate {
+    /// Amount per kWh in the tariff currency.
+    pub amount: Decimal,
+    pub weekdays: Option<Vec<Weekday>>,
+    /// Local hours of the day, e.g. `6..22`, or `22..6` for the night.
+    pub hours: Option<Range<u32>>,
+    /// Months of the year, 1-12.
+    pub months: Option<Vec<u32>>,
+    pub season: Option<Season>,
+    /// Whether the rate applies only on (`true`) or only outside (`false`)
+    /// the tariff's holidays.
+    pub holiday: Option<bool>,
+    /// First day the rate is valid.
+    pub from: Option<NaiveDate>,
+    /// Last day the rate is valid.
+    pub to: Option<NaiveDate>,
+}
+
+impl Rate {
+    pub fn flat(amount: Decimal) -> Self {
+        Self {
+            amount,
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, start_time: DateTime<Tz>, holiday: bool) -> bool {
+        let date = start_time.date_naive();
+        self.weekdays
+            .as_ref()
+            .is_none_or(|days| days.contains(&start_time.weekday()))
+            && self
+                .hours
+                .as_ref()
+                .is_none_or(|hours| in_hours(hours, start_time.hour()))
+            && self
+                .months
+                .as_ref()
+                .is_none_or(|months| months.contains(&start_time.month()))
+            && self.season.is_none_or(|season| season.contains(date))
+            && self.holiday.is_none_or(|h| h == holiday)
+            && in_force(self.from, self.to, date)
+    }
+}
+
+/// Whether `hour` is in `hours`, which wraps around midnight when it starts
+/// after it ends.
+pub(crate) fn in_hours(hours: &Range<u32>, hour: u32) -> bool {
+    if hours.start <= hours.end {
+        hours.contains(&hour)
+    } else {
+        hour >= hours.start || hour < hours.end
+    }
+}
+
+fn in_force(from: Option<NaiveDate>, to: Option<NaiveDate>, date: NaiveDate) -> bool {
+    from.is_none_or(|from| from <= date) && to.is_none_or(|to| date <= to)
+}
+
+/// A named part of the tariff. The first of its rates that matches applies,
+/// if none matches the component is not charged.
+///
+/// A component that changes over time, like the yearly energy tax, is
+/// described by several components with the same name and non-overlapping
+/// effective periods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub kind: ComponentKind,
+    pub rates: Vec<Rate>,
+    /// First day the component is in force.
+    pub effective_from: Option<NaiveDate>,
+    /// Last day the component is in force.
+    pub effective_to: Option<NaiveDate>,
+    /// Whether VAT is charged on the component.
+    pub vat_liable: bool,
+}
+
+impl Component {
+    pub fn is_in_force(&self, date: NaiveDate) -> bool {
+        in_force(self.effective_from, self.effective_to, date)
+    }
+
+    pub fn amount(&self, start_time: DateTime<Tz>, holiday: bool) -> Decimal {
+        self.rates
+            .iter()
+            .find(|r| r.matches(start_time, holiday))
+            .map_or(Decimal::ZERO, |r| r.amount)
+    }
+}
+
+/// A charge billed per month regardless of consumption, e.g. a grid
+/// subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedCharge {
+    pub name: String,
+    /// Amount per month in the tariff currency.
+    pub monthly: Decimal,
+}
+
+/// A VAT rate, e.g. `0.25` for 25%, and the period it is in force.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VatRate {
+    pub rate: Decimal,
+    pub effective_from: Option<NaiveDate>,
+    pub effective_to: Option<NaiveDate>,
+}
+
+impl VatRate {
+    pub fn new(rate: Decimal) -> Self {
+        Self {
+            rate,
+            effective_from: None,
+            effective_to: None,
+        }
+    }
+
+    pub fn is_in_force(&self, date: NaiveDate) -> bool {
+        in_force(self.effective_from, self.effective_to, date)
+    }
+}
+
+/// Describes how the total price is built on top of the spot price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tariff {
+    pub currency: &'static Currency,
+    pub components: Vec<Component>,
+    pub fixed_charges: Vec<FixedCharge>,
+    pub vat: Vec<VatRate>,
+    /// Public holidays billed as holidays by rates with `holiday` set.
+    pub calendar: Option<HolidayCalendar>,
+    /// Additional days billed as holidays.
+    pub holidays: Vec<NaiveDate>,
+    /// Network charge on the monthly peaks, billed next to the per-kWh
+    /// components.
+    pub power: Option<PowerTariff>,
+}
+
+impl Tariff {
+    /// The components in force at `start_time` with their amounts.
+    pub fn components_at(
+        &self,
+        start_time: DateTime<Tz>,
+    ) -> impl Iterator<Item = (&Component, Decimal)> {
+        let date = start_time.date_naive();
+        let holiday = self.is_holiday(date);
+        self.components
+            .iter()
+            .filter(move |c| c.is_in_force(date))
+            .map(move |c| (c, c.amount(start_time, holiday)))
+    }
+
+    /// Sum of the components of `kind` in force and applying at `start_time`.
+    pub fn amount(&self, kind: ComponentKind, start_time: DateTime<Tz>) -> Decimal {
+        self.components_at(start_time)
+            .filter(|(c, _)| c.kind == kind)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    /// The VAT rate in force at `start_time`, zero if there is none.
+    pub fn vat_rate(&self, start_time: DateTime<Tz>) -> Decimal {
+        let date = start_time.date_naive();
+        self.vat
+            .iter()
+            .find(|v| v.is_in_force(date))
+            .map_or(Decimal::ZERO, |v| v.rate)
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date) || self.calendar.is_some_and(|c| c.is_holiday(date))
+    }
+
+    /// Sum of the fixed monthly charges, excluding VAT.
+    pub fn monthly_fixed(&self) -> Decimal {
+        self.fixed_charges.iter().map(|c| c.monthly).sum()
+    }
+
+    /// The monthly bills for `consumption` priced at `prices`, like
+    /// [`PowerTariff::compute`], adding the fixed charges and VAT on the
+    /// fixed and power charges.
+    pub fn bills(
+        &self,
+        consumption: &[(DateTime<Tz>, Decimal)],
+        prices: &[TotalPrice],
+    ) -> Vec<MonthlyBill> {
+        power::bills(
+            self.power.as_ref(),
+            self.currency,
+            consumption,
+            prices,
+            self.monthly_fixed(),
+            |t| self.vat_rate(t),
+        )
+    }
+}
+
+impl Default for Tariff {
+    /// A Swedish time-of-use grid fee of 70 öre on weekdays 06-22 and 12 öre
+    /// otherwise, 45 öre energy tax and 25% VAT on all of them.
+    fn default() -> Self {
+        Self {
+            currency: iso::SEK,
+            components: vec![
+                Component {
+                    name: "Grid fee".to_string(),
+                    kind: ComponentKind::Fee,
+                    rates: vec![
+                        Rate {
+                            amount: dec!(0.70),
+                            weekdays: Some(vec![
+                                Weekday::Mon,
+                                Weekday::Tue,
+                                Weekday::Wed,
+                                Weekday::Thu,
+                                Weekday::Fri,
+                            ]),
+                            hours: Some(6..22),
+                            ..Default::default()
+                        },
+                        Rate::flat(dec!(0.12)),
+                    ],
+                    effective_from: None,
+                    effective_to: None,
+                    vat_liable: true,
+                },
+                Component {
+                    name: "Energy tax".to_string(),
+                    kind: ComponentKind::Tax,
+                    rates: vec![Rate::flat(dec!(0.45))],
+                    effective_from: None,
+                    effective_to: None,
+                    vat_liable: true,
+                },
+            ],
+            fixed_charges: vec![],
+            vat: vec![VatRate::new(dec!(0.25))],
+            calendar: None,
+            holidays: vec![],
+            power: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Weekday};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+
+    use super::{Component, ComponentKind, Rate, Season, Tariff};
+
+    #[test]
+    fn seasonal_rates() {
+        let tariff = Tariff {
+            components: vec![Component {
+                name: "Grid fee".to_string(),
+                kind: ComponentKind::Fee,
+                rates: vec![
+                    Rate {
+                        amount: dec!(0.60),
+                        weekdays: Some(vec![Weekday::Mon, Weekday::Tue]),
+                        hours: Some(6..22),
+                        season: Some(Season::WINTER),
+                        ..Default::default()
+                    },
+                    Rate::flat(dec!(0.20)),
+                ],
+                effective_from: None,
+                effective_to: None,
+                vat_liable: true,
+            }],
+            ..Default::default()
+        };
+        let tuesday = |m, d| Stockholm.ymd(2023, m, d).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, tuesday(1, 3)), dec!(0.60));
+        assert_eq!(
+            tariff.amount(ComponentKind::Fee, tuesday(3, 28)),
+            dec!(0.60)
+        );
+        assert_eq!(tariff.amount(ComponentKind::Fee, tuesday(4, 4)), dec!(0.20));
+        assert_eq!(
+            tariff.amount(ComponentKind::Fee, tuesday(10, 31)),
+            dec!(0.20)
+        );
+        assert_eq!(
+            tariff.amount(ComponentKind::Fee, tuesday(11, 7)),
+            dec!(0.60)
+        );
+    }
+
+    #[test]
+    fn default_tariff() {
+        let tariff = Tariff::default();
+        // Wednesday
+        let weekday = |h| Stockholm.ymd(2022, 11, 9).and_hms(h, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, weekday(5)), dec!(0.12));
+        assert_eq!(tariff.amount(ComponentKind::Fee, weekday(6)), dec!(0.70));
+        assert_eq!(tariff.amount(ComponentKind::Fee, weekday(21)), dec!(0.70));
+        assert_eq!(tariff.amount(ComponentKind::Fee, weekday(22)), dec!(0.12));
+        let saturday = Stockholm.ymd(2022, 11, 12).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, saturday), dec!(0.12));
+        assert_eq!(tariff.amount(ComponentKind::Tax, saturday), dec!(0.45));
+        assert_eq!(tariff.amount(ComponentKind::Energy, saturday), dec!(0));
+    }
+}
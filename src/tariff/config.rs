@@ -0,0 +1,689 @@
+//! Serde description of a [`Tariff`], so tariffs can be kept in TOML or JSON
+//! files and updated without recompiling.
+//!
+//! ```toml
+//! currency = "SEK"
+//! vat = 0.25
+//! calendar = "SE"
+//! holidays = ["2023-12-27"]
+//!
+//! [[components]]
+//! name = "Grid fee"
+//! kind = "fee"
+//! rates = [
+//!     { amount = 0.70, weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri"], hours = [6, 22], holiday = false },
+//!     { amount = 0.12 },
+//! ]
+//!
+//! [[components]]
+//! name = "Energy tax"
+//! kind = "tax"
+//! effective_to = "2022-12-31"
+//! rates = [{ amount = 0.45 }]
+//!
+//! [[components]]
+//! name = "Certificate fee"
+//! kind = "energy"
+//! vat_liable = false
+//! rates = [{ amount = 0.02 }]
+//!
+//! [[components]]
+//! name = "Energy tax"
+//! kind = "tax"
+//! effective_from = "2023-01-01"
+//! rates = [{ amount = 0.392 }]
+//!
+//! [[fixed]]
+//! name = "Subscription"
+//! monthly = 320
+//!
+//! [power]
+//! price_per_kw = 81.25
+//! peaks = 3
+//! one_per_day = true
+//! hours = [7, 19]
+//! exclude_holidays = true
+//! ```
+
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use chrono::{NaiveDate, Weekday};
+use rust_decimal::Decimal;
+use rusty_money::iso;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use super::{
+    Component, ComponentKind, FixedCharge, PeakRule, PowerTariff, Rate, Season, Tariff, VatRate,
+};
+use crate::HolidayCalendar;
+
+#[derive(Debug)]
+pub enum TariffError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `.toml` nor `.json`.
+    UnknownFormat(String),
+    /// The file parsed but describes an invalid tariff. `path` points at the
+    /// offending entry, e.g. `components[0].rates[1].hours`.
+    Invalid {
+        path: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for TariffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TariffError::Io(e) => write!(f, "failed to read tariff: {e}"),
+            TariffError::Toml(e) => write!(f, "malformed tariff: {e}"),
+            TariffError::Json(e) => write!(f, "malformed tariff: {e}"),
+            TariffError::UnknownFormat(path) => {
+                write!(f, "unknown tariff format '{path}', expected .toml or .json")
+            }
+            TariffError::Invalid { path, message } => write!(f, "{path}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TariffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TariffError::Io(e) => Some(e),
+            TariffError::Toml(e) => Some(e),
+            TariffError::Json(e) => Some(e),
+            TariffError::UnknownFormat(_) | TariffError::Invalid { .. } => None,
+        }
+    }
+}
+
+fn invalid(path: impl Into<String>, message: impl Into<String>) -> TariffError {
+    TariffError::Invalid {
+        path: path.into(),
+        message: message.into(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TariffFile {
+    currency: String,
+    /// Either a single rate, `vat = 0.25`, or a list of [`VatPeriodFile`]s.
+    vat: Value,
+    #[serde(default)]
+    components: Vec<ComponentFile>,
+    #[serde(default)]
+    fixed: Vec<FixedFile>,
+    calendar: Option<HolidayCalendar>,
+    #[serde(default)]
+    holidays: Vec<NaiveDate>,
+    power: Option<PowerFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PowerFile {
+    price_per_kw: Decimal,
+    peaks: usize,
+    #[serde(default)]
+    one_per_day: bool,
+    hours: Option<[u32; 2]>,
+    weekdays: Option<Vec<Weekday>>,
+    season: Option<Value>,
+    /// Skip the holidays of the tariff's `calendar`.
+    #[serde(default)]
+    exclude_holidays: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ComponentFile {
+    name: String,
+    kind: ComponentKind,
+    rates: Vec<RateFile>,
+    effective_from: Option<NaiveDate>,
+    effective_to: Option<NaiveDate>,
+    #[serde(default = "default_vat_liable")]
+    vat_liable: bool,
+}
+
+fn default_vat_liable() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VatPeriodFile {
+    rate: Decimal,
+    effective_from: Option<NaiveDate>,
+    effective_to: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RateFile {
+    amount: Decimal,
+    weekdays: Option<Vec<Weekday>>,
+    /// `[from, to)` in local hours, `[22, 6]` for the night.
+    hours: Option<[u32; 2]>,
+    months: Option<Vec<u32>>,
+    season: Option<Value>,
+    holiday: Option<bool>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeasonRangeFile {
+    from: String,
+    to: String,
+}
+
+/// Deserializes the entry at `path`, reporting a mismatch as invalid.
+fn parse<T: DeserializeOwned>(path: &str, value: Value) -> Result<T, TariffError> {
+    serde_json::from_value(value).map_err(|e| invalid(path, e.to_string()))
+}
+
+/// Parses `"winter"`, `"summer"` or `{ from = "11-01", to = "03-31" }`.
+fn parse_season(path: &str, value: Value) -> Result<Season, TariffError> {
+    let day_of_year = |field: &str, s: &str| {
+        s.split_once('-')
+            .and_then(|(m, d)| Some((m.parse().ok()?, d.parse().ok()?)))
+            .filter(|&(m, d)| NaiveDate::from_ymd_opt(2000, m, d).is_some())
+            .ok_or_else(|| {
+                invalid(
+                    format!("{path}.{field}"),
+                    format!("'{s}' is not a MM-DD day of the year"),
+                )
+            })
+    };
+    match value {
+        Value::String(name) => match name.as_str() {
+            "winter" => Ok(Season::WINTER),
+            "summer" => Ok(Season::SUMMER),
+            _ => Err(invalid(path, format!("unknown season '{name}'"))),
+        },
+        value @ Value::Object(_) => {
+            let range: SeasonRangeFile = parse(path, value)?;
+            Ok(Season {
+                from: day_of_year("from", &range.from)?,
+                to: day_of_year("to", &range.to)?,
+            })
+        }
+        _ => Err(invalid(
+            path,
+            "expected \"winter\", \"summer\" or { from, to }",
+        )),
+    }
+}
+
+/// Parses a single rate, `vat = 0.25`, or a list of rates with effective
+/// periods that do not overlap.
+fn parse_vat(value: Value) -> Result<Vec<VatRate>, TariffError> {
+    let Value::Array(periods) = value else {
+        let rate = parse("vat", value)?;
+        check_vat_rate("vat", rate)?;
+        return Ok(vec![VatRate::new(rate)]);
+    };
+    let mut vat: Vec<VatRate> = Vec::with_capacity(periods.len());
+    for (i, v) in periods.into_iter().enumerate() {
+        let path = format!("vat[{i}]");
+        let v: VatPeriodFile = parse(&path, v)?;
+        check_vat_rate(&format!("{path}.rate"), v.rate)?;
+        let period = (v.effective_from, v.effective_to);
+        check_period(&path, period)?;
+        if let Some(j) = vat
+            .iter()
+            .position(|o| overlaps((o.effective_from, o.effective_to), period))
+        {
+            return Err(invalid(path, format!("overlaps vat[{j}]")));
+        }
+        vat.push(VatRate {
+            rate: v.rate,
+            effective_from: v.effective_from,
+            effective_to: v.effective_to,
+        });
+    }
+    Ok(vat)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixedFile {
+    name: String,
+    monthly: Decimal,
+}
+
+type Period = (Option<NaiveDate>, Option<NaiveDate>);
+
+fn check_period(path: &str, (from, to): Period) -> Result<(), TariffError> {
+    match (from, to) {
+        (Some(from), Some(to)) if from > to => Err(invalid(
+            format!("{path}.effective_from"),
+            format!("{from} is after {to}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn overlaps((a_from, a_to): Period, (b_from, b_to): Period) -> bool {
+    let starts_before_end = |from: Option<NaiveDate>, to: Option<NaiveDate>| match (from, to) {
+        (Some(from), Some(to)) => from <= to,
+        _ => true,
+    };
+    starts_before_end(a_from, b_to) && starts_before_end(b_from, a_to)
+}
+
+fn check_vat_rate(path: &str, rate: Decimal) -> Result<(), TariffError> {
+    if rate < Decimal::ZERO || rate > Decimal::ONE {
+        return Err(invalid(
+            path,
+            format!("{rate} is not a rate between 0 and 1"),
+        ));
+    }
+    Ok(())
+}
+
+/// Accepts `[from, to)` hours within 0-24, wrapping around midnight when
+/// `from` is after `to`, e.g. `[22, 6]`.
+fn check_hours(path: &str, hours: Option<[u32; 2]>) -> Result<(), TariffError> {
+    match hours {
+        Some([from, to]) if from == to || from > 23 || to > 24 => Err(invalid(
+            format!("{path}.hours"),
+            format!("[{from}, {to}] is not a range within 0-24"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl RateFile {
+    fn into_rate(self, path: &str) -> Result<Rate, TariffError> {
+        check_hours(path, self.hours)?;
+        if let Some(month) = self
+            .months
+            .iter()
+            .flatten()
+            .find(|m| !(1..=12).contains(*m))
+        {
+            return Err(invalid(
+                format!("{path}.months"),
+                format!("{month} is not a month"),
+            ));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(invalid(
+                    format!("{path}.from"),
+                    format!("{from} is after {to}"),
+                ));
+            }
+        }
+        Ok(Rate {
+            amount: self.amount,
+            weekdays: self.weekdays,
+            hours: self.hours.map(|[from, to]| from..to),
+            months: self.months,
+            season: self
+                .season
+                .map(|s| parse_season(&format!("{path}.season"), s))
+                .transpose()?,
+            holiday: self.holiday,
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
+
+impl TryFrom<TariffFile> for Tariff {
+    type Error = TariffError;
+
+    fn try_from(file: TariffFile) -> Result<Self, Self::Error> {
+        let currency = iso::find(&file.currency)
+            .ok_or_else(|| invalid("currency", format!("unknown currency '{}'", file.currency)))?;
+        let vat = parse_vat(file.vat)?;
+        let mut components: Vec<Component> = Vec::with_capacity(file.components.len());
+        for (i, c) in file.components.into_iter().enumerate() {
+            let path = format!("components[{i}]");
+            if c.name.is_empty() {
+                return Err(invalid(format!("{path}.name"), "empty name"));
+            }
+            if c.rates.is_empty() {
+                return Err(invalid(format!("{path}.rates"), "no rates"));
+            }
+            let period = (c.effective_from, c.effective_to);
+            check_period(&path, period)?;
+            if let Some(j) = components.iter().position(|o| {
+                o.name == c.name && overlaps((o.effective_from, o.effective_to), period)
+            }) {
+                return Err(invalid(
+                    path,
+                    format!("'{}' overlaps components[{j}]", c.name),
+                ));
+            }
+            let rates = c
+                .rates
+                .into_iter()
+                .enumerate()
+                .map(|(j, r)| r.into_rate(&format!("{path}.rates[{j}]")))
+                .collect::<Result<_, _>>()?;
+            components.push(Component {
+                name: c.name,
+                kind: c.kind,
+                rates,
+                effective_from: c.effective_from,
+                effective_to: c.effective_to,
+                vat_liable: c.vat_liable,
+            });
+        }
+        let fixed_charges = file
+            .fixed
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if c.name.is_empty() {
+                    return Err(invalid(format!("fixed[{i}].name"), "empty name"));
+                }
+                Ok(FixedCharge {
+                    name: c.name,
+                    monthly: c.monthly,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let power = file
+            .power
+            .map(|p| {
+                if p.peaks == 0 {
+                    return Err(invalid("power.peaks", "must be at least 1"));
+                }
+                check_hours("power", p.hours)?;
+                if p.exclude_holidays && file.calendar.is_none() {
+                    return Err(invalid(
+                        "power.exclude_holidays",
+                        "requires a holiday calendar",
+                    ));
+                }
+                Ok(PowerTariff {
+                    currency,
+                    price_per_kw: p.price_per_kw,
+                    rule: PeakRule {
+                        peaks: p.peaks,
+                        one_per_day: p.one_per_day,
+                        hours: p.hours.map(|[from, to]| from..to),
+                        weekdays: p.weekdays,
+                        season: p
+                            .season
+                            .map(|s| parse_season("power.season", s))
+                            .transpose()?,
+                        exclude_holidays: file.calendar.filter(|_| p.exclude_holidays),
+                    },
+                })
+            })
+            .transpose()?;
+        Ok(Tariff {
+            currency,
+            components,
+            fixed_charges,
+            vat,
+            calendar: file.calendar,
+            holidays: file.holidays,
+            power,
+        })
+    }
+}
+
+impl Tariff {
+    pub fn from_toml(s: &str) -> Result<Self, TariffError> {
+        toml::from_str::<TariffFile>(s)
+            .map_err(TariffError::Toml)?
+            .try_into()
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, TariffError> {
+        serde_json::from_str::<TariffFile>(s)
+            .map_err(TariffError::Json)?
+            .try_into()
+    }
+
+    /// Loads a tariff from a `.toml` or `.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TariffError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(TariffError::Io)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(TariffError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+impl FromStr for Tariff {
+    type Err = TariffError;
+
+    /// Parses a TOML tariff description.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_toml(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal_macros::dec;
+
+    use super::TariffError;
+    use crate::{ComponentKind, Tariff};
+
+    const TARIFF: &str = r#"
+        currency = "SEK"
+        vat = 0.25
+        holidays = ["2022-12-26"]
+
+        [[components]]
+        name = "Grid fee"
+        kind = "fee"
+        rates = [
+            { amount = 0.70, weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri"], hours = [6, 22], holiday = false },
+            { amount = 0.12 },
+        ]
+
+        [[components]]
+        name = "Energy tax"
+        kind = "tax"
+        rates = [{ amount = 0.45 }]
+
+        [[fixed]]
+        name = "Subscription"
+        monthly = 320
+    "#;
+
+    #[test]
+    fn parse_toml() {
+        let tariff: Tariff = TARIFF.parse().unwrap();
+        let default = Tariff::default();
+        let monday = Stockholm.ymd(2022, 12, 19).and_hms(12, 0, 0);
+        for kind in [ComponentKind::Fee, ComponentKind::Tax] {
+            assert_eq!(tariff.amount(kind, monday), default.amount(kind, monday));
+        }
+        let boxing_day = Stockholm.ymd(2022, 12, 26).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, boxing_day), dec!(0.12));
+        assert_eq!(tariff.monthly_fixed(), dec!(320));
+        assert!(tariff.is_holiday(NaiveDate::from_ymd(2022, 12, 26)));
+    }
+
+    #[test]
+    fn holiday_calendar() {
+        let tariff: Tariff = TARIFF
+            .replace("holidays = [\"2022-12-26\"]", "calendar = \"SE\"")
+            .parse()
+            .unwrap();
+        let midsummer_eve = Stockholm.ymd(2023, 6, 23).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, midsummer_eve), dec!(0.12));
+        let day_before = Stockholm.ymd(2023, 6, 22).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, day_before), dec!(0.70));
+    }
+
+    #[test]
+    fn seasons() {
+        let tariff: Tariff = r#"
+            currency = "SEK"
+            vat = 0.25
+
+            [[components]]
+            name = "Grid fee"
+            kind = "fee"
+            rates = [
+                { amount = 0.60, season = "winter", weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri"], hours = [6, 22] },
+                { amount = 0.30, season = { from = "06-15", to = "08-15" } },
+                { amount = 0.20 },
+            ]
+        "#
+        .parse()
+        .unwrap();
+        let at = |m, d| Stockholm.ymd(2023, m, d).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(2, 1)), dec!(0.60));
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(7, 3)), dec!(0.30));
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(9, 4)), dec!(0.20));
+    }
+
+    #[test]
+    fn overnight_hours() {
+        let tariff: Tariff = TARIFF
+            .replace("hours = [6, 22]", "hours = [22, 6]")
+            .parse()
+            .unwrap();
+        // Monday to Tuesday
+        let at = |d, h| Stockholm.ymd(2022, 12, d).and_hms(h, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(19, 23)), dec!(0.70));
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(20, 3)), dec!(0.70));
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(20, 6)), dec!(0.12));
+        assert_eq!(tariff.amount(ComponentKind::Fee, at(19, 12)), dec!(0.12));
+    }
+
+    #[test]
+    fn power_tariff() {
+        let tariff: Tariff = format!(
+            "{TARIFF}\n[power]\nprice_per_kw = 81.25\npeaks = 3\none_per_day = true\nhours = [7, 19]"
+        )
+        .parse()
+        .unwrap();
+        let power = tariff.power.unwrap();
+        assert_eq!(power.price_per_kw, dec!(81.25));
+        assert_eq!(power.rule.peaks, 3);
+        assert_eq!(power.rule.hours, Some(7..19));
+
+        let err = format!("{TARIFF}\n[power]\nprice_per_kw = 81.25\npeaks = 0")
+            .parse::<Tariff>()
+            .unwrap_err();
+        assert!(matches!(err, TariffError::Invalid { path, .. } if path == "power.peaks"));
+    }
+
+    #[test]
+    fn parse_json() {
+        let tariff = Tariff::from_json(
+            r#"{
+                "currency": "EUR",
+                "vat": 0.24,
+                "components": [{ "name": "Margin", "kind": "energy", "rates": [{ "amount": 0.004 }] }]
+            }"#,
+        )
+        .unwrap();
+        let t = Stockholm.ymd(2022, 12, 19).and_hms(12, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Energy, t), dec!(0.004));
+    }
+
+    #[test]
+    fn effective_periods() {
+        let tariff: Tariff = r#"
+            currency = "SEK"
+            vat = [
+                { rate = 0.25, effective_to = "2022-06-30" },
+                { rate = 0.06, effective_from = "2022-07-01", effective_to = "2022-07-31" },
+                { rate = 0.25, effective_from = "2022-08-01" },
+            ]
+
+            [[components]]
+            name = "Energy tax"
+            kind = "tax"
+            effective_to = "2022-12-31"
+            rates = [{ amount = 0.45 }]
+
+            [[components]]
+            name = "Energy tax"
+            kind = "tax"
+            effective_from = "2023-01-01"
+            rates = [{ amount = 0.392 }]
+        "#
+        .parse()
+        .unwrap();
+        let dec31 = Stockholm.ymd(2022, 12, 31).and_hms(23, 0, 0);
+        let jan1 = Stockholm.ymd(2023, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(tariff.amount(ComponentKind::Tax, dec31), dec!(0.45));
+        assert_eq!(tariff.amount(ComponentKind::Tax, jan1), dec!(0.392));
+        assert_eq!(tariff.vat_rate(dec31), dec!(0.25));
+        let july = Stockholm.ymd(2022, 7, 15).and_hms(12, 0, 0);
+        assert_eq!(tariff.vat_rate(july), dec!(0.06));
+    }
+
+    #[test]
+    fn reports_overlapping_components() {
+        let err = TARIFF
+            .replace(
+                "name = \"Energy tax\"",
+                "name = \"Grid fee\"\n        effective_from = \"2023-01-01\"",
+            )
+            .parse::<Tariff>()
+            .unwrap_err();
+        match err {
+            TariffError::Invalid { path, .. } => assert_eq!(path, "components[1]"),
+            e => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn reports_malformed_rule() {
+        let err = TARIFF
+            .replace("hours = [6, 22]", "hours = [6, 6]")
+            .parse::<Tariff>()
+            .unwrap_err();
+        match err {
+            TariffError::Invalid { path, .. } => assert_eq!(path, "components[0].rates[0].hours"),
+            e => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn reports_malformed_vat_and_season() {
+        let err = TARIFF
+            .replace(
+                "vat = 0.25",
+                "vat = [{ rate = 0.25, effective_to = \"2022-06-30\" }, { rat = 0.06 }]",
+            )
+            .parse::<Tariff>()
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("vat[1]: unknown field `rat`"),
+            "{err}"
+        );
+
+        let err = TARIFF
+            .replace("vat = 0.25", "vat = \"high\"")
+            .parse::<Tariff>()
+            .unwrap_err();
+        assert!(matches!(err, TariffError::Invalid { path, .. } if path == "vat"));
+
+        let err = TARIFF
+            .replace(
+                "{ amount = 0.12 }",
+                "{ amount = 0.12, season = { from = \"06-15\" } }",
+            )
+            .parse::<Tariff>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "components[0].rates[1].season: missing field `to`"
+        );
+    }
+}
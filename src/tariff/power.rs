@@ -0,0 +1,323 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Weekday};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use rusty_money::iso::Currency;
+
+use super::{in_hours, Season};
+use crate::{HolidayCalendar, Money, TotalPrice};
+
+/// Which hourly peaks a power tariff bills, e.g. the average of the three
+/// highest hours of the month on different days between 07 and 19.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PeakRule {
+    /// Number of peaks averaged per month.
+    pub peaks: usize,
+    /// Count at most one peak per day.
+    pub one_per_day: bool,
+    /// Local hours the peaks are measured in, e.g. `7..19`, wrapping around
+    /// midnight when they start after they end.
+    pub hours: Option<Range<u32>>,
+    pub weekdays: Option<Vec<Weekday>>,
+    pub season: Option<Season>,
+    /// Do not measure peaks on holidays of this calendar.
+    pub exclude_holidays: Option<HolidayCalendar>,
+}
+
+impl PeakRule {
+    fn measures(&self, start_time: DateTime<Tz>) -> bool {
+        let date = start_time.date_naive();
+        self.hours
+            .as_ref()
+            .is_none_or(|hours| in_hours(hours, start_time.hour()))
+            && self
+                .weekdays
+                .as_ref()
+                .is_none_or(|days| days.contains(&start_time.weekday()))
+            && self.season.is_none_or(|season| season.contains(date))
+            && self
+                .exclude_holidays
+                .is_none_or(|calendar| !calendar.is_holiday(date))
+    }
+}
+
+/// A network charge per kW of the monthly billing peaks (effekttariff).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerTariff {
+    pub currency: &'static Currency,
+    /// Amount per kW and month.
+    pub price_per_kw: Decimal,
+    pub rule: PeakRule,
+}
+
+/// An hour counted as a billing peak.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peak {
+    pub start_time: DateTime<Tz>,
+    pub kw: Decimal,
+}
+
+/// Power charge, fixed charges and energy cost of one calendar month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyBill {
+    /// First day of the month.
+    pub month: NaiveDate,
+    pub peaks: Vec<Peak>,
+    /// Average of the peaks.
+    pub average_kw: Decimal,
+    /// Charge for the peaks, excluding VAT.
+    pub power_charge: Money,
+    /// The fixed monthly charges of the tariff, excluding VAT.
+    pub fixed_charges: Money,
+    /// VAT on the power and fixed charges.
+    pub vat: Money,
+    /// Consumption times [`TotalPrice::sum`] of the interval it falls in,
+    /// including VAT.
+    pub energy_cost: Money,
+    pub consumption_kwh: Decimal,
+}
+
+impl MonthlyBill {
+    /// The amount to pay including VAT.
+    pub fn total(&self) -> Money {
+        self.power_charge.clone()
+            + self.fixed_charges.clone()
+            + self.vat.clone()
+            + self.energy_cost.clone()
+    }
+}
+
+/// Consumption of one month, summed per clock hour.
+struct Month {
+    first: DateTime<Tz>,
+    hours: BTreeMap<DateTime<Tz>, Decimal>,
+    energy_cost: Money,
+    consumption_kwh: Decimal,
+}
+
+fn hour_start(t: DateTime<Tz>) -> DateTime<Tz> {
+    t - Duration::minutes(t.minute().into())
+        - Duration::seconds(t.second().into())
+        - Duration::nanoseconds(t.nanosecond().into())
+}
+
+fn month_start(t: DateTime<Tz>) -> NaiveDate {
+    NaiveDate::from_ymd(t.year(), t.month(), 1)
+}
+
+impl PowerTariff {
+    /// Computes the monthly bills for `consumption`, a series of interval
+    /// start times and the kWh consumed in each interval. `prices` must be in
+    /// the power tariff currency.
+    ///
+    /// Consumption is summed per clock hour, so the series may have any
+    /// resolution up to one hour. Energy cost uses the price in `prices` of
+    /// the interval each consumption starts in; consumption outside the
+    /// prices is not priced. The bills have no fixed charges and no VAT on
+    /// the power charge, see [`Tariff::bills`](crate::Tariff::bills) for
+    /// those.
+    pub fn compute(
+        &self,
+        consumption: &[(DateTime<Tz>, Decimal)],
+        prices: &[TotalPrice],
+    ) -> Vec<MonthlyBill> {
+        bills(
+            Some(self),
+            self.currency,
+            consumption,
+            prices,
+            Decimal::ZERO,
+            |_| Decimal::ZERO,
+        )
+    }
+
+    /// The billing peaks among hourly consumption of one month, highest
+    /// first.
+    fn peaks(&self, hours: BTreeMap<DateTime<Tz>, Decimal>) -> Vec<Peak> {
+        let mut candidates: Vec<Peak> = hours
+            .into_iter()
+            .filter(|(start_time, _)| self.rule.measures(*start_time))
+            .map(|(start_time, kw)| Peak { start_time, kw })
+            .collect();
+        candidates.sort_by(|a, b| b.kw.cmp(&a.kw).then(a.start_time.cmp(&b.start_time)));
+        let mut peaks: Vec<Peak> = Vec::with_capacity(self.rule.peaks);
+        for candidate in candidates {
+            if peaks.len() == self.rule.peaks {
+                break;
+            }
+            if self.rule.one_per_day
+                && peaks
+                    .iter()
+                    .any(|p| p.start_time.date_naive() == candidate.start_time.date_naive())
+            {
+                continue;
+            }
+            peaks.push(candidate);
+        }
+        peaks
+    }
+}
+
+/// Monthly bills with the power charge of `power`, if any, and `fixed`
+/// charged per month, both with VAT at `vat_rate` of the first consumption
+/// of the month.
+pub(crate) fn bills(
+    power: Option<&PowerTariff>,
+    currency: &'static Currency,
+    consumption: &[(DateTime<Tz>, Decimal)],
+    prices: &[TotalPrice],
+    fixed: Decimal,
+    vat_rate: impl Fn(DateTime<Tz>) -> Decimal,
+) -> Vec<MonthlyBill> {
+    let mut prices: Vec<&TotalPrice> = prices.iter().collect();
+    prices.sort_by_key(|p| p.start_time());
+
+    let mut months: BTreeMap<NaiveDate, Month> = BTreeMap::new();
+    for &(start_time, kwh) in consumption {
+        let month = months
+            .entry(month_start(start_time))
+            .or_insert_with(|| Month {
+                first: start_time,
+                hours: BTreeMap::new(),
+                energy_cost: Money::from_minor(0, currency),
+                consumption_kwh: Decimal::ZERO,
+            });
+        month.first = month.first.min(start_time);
+        *month.hours.entry(hour_start(start_time)).or_default() += kwh;
+        month.consumption_kwh += kwh;
+        let i = prices.partition_point(|p| p.start_time() <= start_time);
+        if i > 0 && start_time < prices[i - 1].end_time() {
+            month.energy_cost += prices[i - 1].sum() * kwh;
+        }
+    }
+
+    months
+        .into_iter()
+        .map(|(month, m)| {
+            let peaks = power.map(|p| p.peaks(m.hours)).unwrap_or_default();
+            let average_kw = if peaks.is_empty() {
+                Decimal::ZERO
+            } else {
+                peaks.iter().map(|p| p.kw).sum::<Decimal>() / Decimal::from(peaks.len())
+            };
+            let power_charge = average_kw * power.map_or(Decimal::ZERO, |p| p.price_per_kw);
+            let vat = (power_charge + fixed) * vat_rate(m.first);
+            MonthlyBill {
+                month,
+                peaks,
+                average_kw,
+                power_charge: Money::from_decimal(power_charge, currency),
+                fixed_charges: Money::from_decimal(fixed, currency),
+                vat: Money::from_decimal(vat, currency),
+                energy_cost: m.energy_cost,
+                consumption_kwh: m.consumption_kwh,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration, TimeZone, Timelike};
+    use chrono_tz::Europe::Stockholm;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use rusty_money::iso::SEK;
+
+    use super::{PeakRule, PowerTariff};
+    use crate::{FixedCharge, Money, Tariff, TotalPrice};
+
+    #[test]
+    fn top_three_one_per_day() {
+        let start = Stockholm.ymd(2023, 1, 2).and_hms(0, 0, 0);
+        let hours: Vec<_> = (0..72).map(|h| start + Duration::hours(h)).collect();
+        let consumption: Vec<_> = hours
+            .iter()
+            .map(|&t| {
+                let kwh = match (t.day(), t.hour()) {
+                    (2, 8) => dec!(5),
+                    (2, 9) => dec!(4.5),
+                    (3, 18) => dec!(3),
+                    (4, 3) => dec!(6),
+                    (4, 12) => dec!(2),
+                    _ => dec!(1),
+                };
+                (t, kwh)
+            })
+            .collect();
+        let prices: Vec<_> = hours
+            .iter()
+            .map(|&t| {
+                TotalPrice::compute(
+                    t,
+                    Duration::hours(1),
+                    Money::from_minor(100, SEK),
+                    &Tariff::default(),
+                )
+            })
+            .collect();
+        let tariff = PowerTariff {
+            currency: SEK,
+            price_per_kw: dec!(50),
+            rule: PeakRule {
+                peaks: 3,
+                one_per_day: true,
+                hours: Some(7..19),
+                ..Default::default()
+            },
+        };
+        let bills = tariff.compute(&consumption, &prices);
+        assert_eq!(bills.len(), 1);
+        let bill = &bills[0];
+        let peaks: Vec<Decimal> = bill.peaks.iter().map(|p| p.kw).collect();
+        assert_eq!(peaks, vec![dec!(5), dec!(3), dec!(2)]);
+        assert_eq!(bill.average_kw, dec!(10) / dec!(3));
+        assert_eq!(bill.power_charge.amount(), &(dec!(10) / dec!(3) * dec!(50)));
+        assert_eq!(bill.consumption_kwh, dec!(87.5));
+        assert!(bill.energy_cost.is_positive());
+    }
+
+    #[test]
+    fn fixed_charges_vat_and_stale_prices() {
+        let start = Stockholm.ymd(2023, 1, 2).and_hms(8, 0, 0);
+        let price = TotalPrice::compute(
+            start,
+            Duration::hours(1),
+            Money::from_minor(100, SEK),
+            &Tariff::default(),
+        );
+        // The second hour has no price, it is not billed at the first.
+        let consumption = [(start, dec!(2)), (start + Duration::days(7), dec!(10))];
+        let mut tariff = Tariff::default();
+        tariff.fixed_charges.push(FixedCharge {
+            name: "Subscription".to_string(),
+            monthly: dec!(320),
+        });
+        tariff.power = Some(PowerTariff {
+            currency: SEK,
+            price_per_kw: dec!(50),
+            rule: PeakRule {
+                peaks: 1,
+                ..Default::default()
+            },
+        });
+
+        let bill = &tariff.bills(&consumption, std::slice::from_ref(&price))[0];
+        assert_eq!(bill.energy_cost, price.sum() * dec!(2));
+        assert_eq!(bill.power_charge.amount(), &dec!(500));
+        assert_eq!(bill.fixed_charges.amount(), &dec!(320));
+        assert_eq!(bill.vat.amount(), &dec!(205));
+        assert_eq!(
+            bill.total().amount(),
+            &(dec!(1025) + price.sum().amount() * dec!(2))
+        );
+
+        let bill = &tariff
+            .power
+            .as_ref()
+            .unwrap()
+            .compute(&consumption, &[price])[0];
+        assert!(bill.vat.is_zero() && bill.fixed_charges.is_zero());
+    }
+}
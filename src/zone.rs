@@ -0,0 +1,299 @@
+use std::{fmt, str::FromStr};
+
+use chrono_tz::{Europe, Tz};
+use rusty_money::iso::{self, Currency};
+
+use crate::HolidayCalendar;
+
+/// A Nord Pool bidding zone (price area).
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BiddingZone {
+    SYS,
+    NO1,
+    NO2,
+    NO3,
+    NO4,
+    NO5,
+    SE1,
+    SE2,
+    SE3,
+    SE4,
+    FI,
+    DK1,
+    DK2,
+    EE,
+    LV,
+    LT,
+    AT,
+    BE,
+    DE,
+    FR,
+    NL,
+    PL,
+}
+
+impl BiddingZone {
+    pub const ALL: [BiddingZone; 22] = [
+        BiddingZone::SYS,
+        BiddingZone::NO1,
+        BiddingZone::NO2,
+        BiddingZone::NO3,
+        BiddingZone::NO4,
+        BiddingZone::NO5,
+        BiddingZone::SE1,
+        BiddingZone::SE2,
+        BiddingZone::SE3,
+        BiddingZone::SE4,
+        BiddingZone::FI,
+        BiddingZone::DK1,
+        BiddingZone::DK2,
+        BiddingZone::EE,
+        BiddingZone::LV,
+        BiddingZone::LT,
+        BiddingZone::AT,
+        BiddingZone::BE,
+        BiddingZone::DE,
+        BiddingZone::FR,
+        BiddingZone::NL,
+        BiddingZone::PL,
+    ];
+
+    /// The area code Nord Pool uses for the zone, e.g. `SE3` or `DE-LU`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BiddingZone::SYS => "SYS",
+            BiddingZone::NO1 => "NO1",
+            BiddingZone::NO2 => "NO2",
+            BiddingZone::NO3 => "NO3",
+            BiddingZone::NO4 => "NO4",
+            BiddingZone::NO5 => "NO5",
+            BiddingZone::SE1 => "SE1",
+            BiddingZone::SE2 => "SE2",
+            BiddingZone::SE3 => "SE3",
+            BiddingZone::SE4 => "SE4",
+            BiddingZone::FI => "FI",
+            BiddingZone::DK1 => "DK1",
+            BiddingZone::DK2 => "DK2",
+            BiddingZone::EE => "EE",
+            BiddingZone::LV => "LV",
+            BiddingZone::LT => "LT",
+            BiddingZone::AT => "AT",
+            BiddingZone::BE => "BE",
+            BiddingZone::DE => "DE-LU",
+            BiddingZone::FR => "FR",
+            BiddingZone::NL => "NL",
+            BiddingZone::PL => "PL",
+        }
+    }
+
+    /// Human readable name of the zone.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            BiddingZone::SYS => "Nordic system price",
+            BiddingZone::NO1 => "Norway, Oslo",
+            BiddingZone::NO2 => "Norway, Kristiansand",
+            BiddingZone::NO3 => "Norway, Molde",
+            BiddingZone::NO4 => "Norway, Tromsø",
+            BiddingZone::NO5 => "Norway, Bergen",
+            BiddingZone::SE1 => "Sweden, Luleå",
+            BiddingZone::SE2 => "Sweden, Sundsvall",
+            BiddingZone::SE3 => "Sweden, Stockholm",
+            BiddingZone::SE4 => "Sweden, Malmö",
+            BiddingZone::FI => "Finland",
+            BiddingZone::DK1 => "Denmark, West",
+            BiddingZone::DK2 => "Denmark, East",
+            BiddingZone::EE => "Estonia",
+            BiddingZone::LV => "Latvia",
+            BiddingZone::LT => "Lithuania",
+            BiddingZone::AT => "Austria",
+            BiddingZone::BE => "Belgium",
+            BiddingZone::DE => "Germany & Luxembourg",
+            BiddingZone::FR => "France",
+            BiddingZone::NL => "Netherlands",
+            BiddingZone::PL => "Poland",
+        }
+    }
+
+    /// Local timezone of the zone, used to interpret delivery hours.
+    pub fn timezone(&self) -> Tz {
+        match self {
+            BiddingZone::SYS => Europe::Oslo,
+            BiddingZone::NO1
+            | BiddingZone::NO2
+            | BiddingZone::NO3
+            | BiddingZone::NO4
+            | BiddingZone::NO5 => Europe::Oslo,
+            BiddingZone::SE1 | BiddingZone::SE2 | BiddingZone::SE3 | BiddingZone::SE4 => {
+                Europe::Stockholm
+            }
+            BiddingZone::FI => Europe::Helsinki,
+            BiddingZone::DK1 | BiddingZone::DK2 => Europe::Copenhagen,
+            BiddingZone::EE => Europe::Tallinn,
+            BiddingZone::LV => Europe::Riga,
+            BiddingZone::LT => Europe::Vilnius,
+            BiddingZone::AT => Europe::Vienna,
+            BiddingZone::BE => Europe::Brussels,
+            BiddingZone::DE => Europe::Berlin,
+            BiddingZone::FR => Europe::Paris,
+            BiddingZone::NL => Europe::Amsterdam,
+            BiddingZone::PL => Europe::Warsaw,
+        }
+    }
+
+    /// The currency prices in this zone are usually quoted in, one of
+    /// [`SUPPORTED_CURRENCIES`](crate::SUPPORTED_CURRENCIES). Nord Pool does
+    /// not publish prices in PLN, so Poland is quoted in EUR.
+    pub fn currency(&self) -> &'static Currency {
+        match self {
+            BiddingZone::NO1
+            | BiddingZone::NO2
+            | BiddingZone::NO3
+            | BiddingZone::NO4
+            | BiddingZone::NO5 => iso::NOK,
+            BiddingZone::SE1 | BiddingZone::SE2 | BiddingZone::SE3 | BiddingZone::SE4 => iso::SEK,
+            BiddingZone::DK1 | BiddingZone::DK2 => iso::DKK,
+            _ => iso::EUR,
+        }
+    }
+
+    /// Public holidays of the country the zone belongs to, for the zones
+    /// that have a calendar.
+    pub fn holiday_calendar(&self) -> Option<HolidayCalendar> {
+        match self {
+            BiddingZone::NO1
+            | BiddingZone::NO2
+            | BiddingZone::NO3
+            | BiddingZone::NO4
+            | BiddingZone::NO5 => Some(HolidayCalendar::Norway),
+            BiddingZone::SE1 | BiddingZone::SE2 | BiddingZone::SE3 | BiddingZone::SE4 => {
+                Some(HolidayCalendar::Sweden)
+            }
+            BiddingZone::FI => Some(HolidayCalendar::Finland),
+            BiddingZone::DK1 | BiddingZone::DK2 => Some(HolidayCalendar::Denmark),
+            BiddingZone::EE => Some(HolidayCalendar::Estonia),
+            BiddingZone::LV => Some(HolidayCalendar::Latvia),
+            BiddingZone::LT => Some(HolidayCalendar::Lithuania),
+            _ => None,
+        }
+    }
+
+    /// Energy Identification Code of the zone's bidding zone area, used by
+    /// the ENTSO-E Transparency Platform. The Nordic system price has none.
+    pub fn eic(&self) -> Option<&'static str> {
+        Some(match self {
+            BiddingZone::SYS => return None,
+            BiddingZone::NO1 => "10YNO-1--------2",
+            BiddingZone::NO2 => "10YNO-2--------T",
+            BiddingZone::NO3 => "10YNO-3--------J",
+            BiddingZone::NO4 => "10YNO-4--------9",
+            BiddingZone::NO5 => "10Y1001A1001A48H",
+            BiddingZone::SE1 => "10Y1001A1001A44P",
+            BiddingZone::SE2 => "10Y1001A1001A45N",
+            BiddingZone::SE3 => "10Y1001A1001A46L",
+            BiddingZone::SE4 => "10Y1001A1001A47J",
+            BiddingZone::FI => "10YFI-1--------U",
+            BiddingZone::DK1 => "10YDK-1--------W",
+            BiddingZone::DK2 => "10YDK-2--------M",
+            BiddingZone::EE => "10Y1001A1001A39I",
+            BiddingZone::LV => "10YLV-1001A00074",
+            BiddingZone::LT => "10YLT-1001A0008Q",
+            BiddingZone::AT => "10YAT-APG------L",
+            BiddingZone::BE => "10YBE----------2",
+            BiddingZone::DE => "10Y1001A1001A82H",
+            BiddingZone::FR => "10YFR-RTE------C",
+            BiddingZone::NL => "10YNL----------L",
+            BiddingZone::PL => "10YPL-AREA-----S",
+        })
+    }
+
+    /// The zone with the Energy Identification Code `eic`.
+    pub fn from_eic(eic: &str) -> Option<BiddingZone> {
+        BiddingZone::ALL
+            .iter()
+            .copied()
+            .find(|z| z.eic() == Some(eic))
+    }
+
+    /// Delivery area code of the zone in the Data Portal API, which labels
+    /// Germany and Luxembourg `GER`.
+    pub(crate) fn delivery_area(&self) -> &'static str {
+        match self {
+            BiddingZone::DE => "GER",
+            _ => self.name(),
+        }
+    }
+
+    /// Column name of the zone in the legacy marketdata page response, which
+    /// labels the Norwegian zones by city.
+    pub(crate) fn column_name(&self) -> &'static str {
+        match self {
+            BiddingZone::NO1 => "Oslo",
+            BiddingZone::NO2 => "Kr.sand",
+            BiddingZone::NO3 => "Molde",
+            BiddingZone::NO4 => "Tromsø",
+            BiddingZone::NO5 => "Bergen",
+            _ => self.name(),
+        }
+    }
+}
+
+impl fmt::Display for BiddingZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBiddingZoneError(String);
+
+impl fmt::Display for ParseBiddingZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown bidding zone '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseBiddingZoneError {}
+
+impl FromStr for BiddingZone {
+    type Err = ParseBiddingZoneError;
+
+    /// Accepts the area code (`SE3`, `DE-LU`, `DE`, `GER`) as well as the
+    /// legacy column names (`Oslo`, `Kr.sand`, ...), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BiddingZone::ALL
+            .iter()
+            .copied()
+            .find(|z| {
+                z.name().eq_ignore_ascii_case(s)
+                    || z.column_name().eq_ignore_ascii_case(s)
+                    || z.delivery_area().eq_ignore_ascii_case(s)
+                    || format!("{z:?}").eq_ignore_ascii_case(s)
+            })
+            .ok_or_else(|| ParseBiddingZoneError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BiddingZone;
+
+    #[test]
+    fn parse_zone() {
+        for zone in BiddingZone::ALL {
+            assert_eq!(zone.name().parse::<BiddingZone>(), Ok(zone));
+            assert_eq!(zone.column_name().parse::<BiddingZone>(), Ok(zone));
+            assert_eq!(zone.delivery_area().parse::<BiddingZone>(), Ok(zone));
+            assert!(crate::SUPPORTED_CURRENCIES.contains(&zone.currency()));
+            if let Some(eic) = zone.eic() {
+                assert_eq!(BiddingZone::from_eic(eic), Some(zone));
+            }
+        }
+        assert_eq!("de".parse::<BiddingZone>(), Ok(BiddingZone::DE));
+        assert_eq!(
+            BiddingZone::from_eic("10Y1001A1001A46L"),
+            Some(BiddingZone::SE3)
+        );
+        assert!("SE5".parse::<BiddingZone>().is_err());
+    }
+}